@@ -0,0 +1,174 @@
+use crate::rnghandle::RngHandle;
+use geister_core::{
+    board::*,
+    outcome::Captured,
+    player::PlayerID,
+};
+use rand::Rng;
+
+/// Fills unknown ghosts of a view with red and blue, consistently with the
+/// numbers of ghosts the opponent has left.
+#[derive(Clone, Debug)]
+pub struct Determinizer {
+    view: Board,
+    unknown: Vec<(Position, OwnedCell)>,
+    /// Consistent assignments. The i-th bit is set if `unknown[i]` is red.
+    assignments: Vec<u16>,
+}
+
+impl Determinizer {
+    /// Returns `None` if no assignment is consistent with `captured` and the ghosts
+    /// of known colors in `view`.
+    pub fn new(view: &Board, player: PlayerID, captured: &Captured) -> Option<Self> {
+        let opponent = player.rev();
+        let unknown: Vec<_> = view
+            .pieces(opponent)
+            .filter(|(_, o)| o.ghost() == Ghost::Unknown)
+            .collect();
+        let n = unknown.len();
+        // ghosts whose color is already known in the view take their share first
+        let red_left = captured
+            .left(opponent, Ghost::Red)
+            .checked_sub(view.count(opponent, Ghost::Red))?;
+        let blue_left = captured
+            .left(opponent, Ghost::Blue)
+            .checked_sub(view.count(opponent, Ghost::Blue))?;
+        // unless some captured ghosts have unknown colors, every red one left is on the board
+        let exact = captured.count(opponent, Ghost::Unknown) == 0;
+        let assignments: Vec<_> = (0..1u16 << n)
+            .filter(|mask| {
+                let red = mask.count_ones() as usize;
+                red <= usize::from(red_left)
+                    && n - red <= usize::from(blue_left)
+                    && (!exact || red == usize::from(red_left))
+            })
+            .collect();
+        if assignments.is_empty() {
+            return None;
+        }
+        Some(Determinizer {
+            view: view.clone(),
+            unknown,
+            assignments,
+        })
+    }
+    /// The number of consistent assignments
+    pub fn len(&self) -> usize {
+        self.assignments.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.assignments.is_empty()
+    }
+    fn board(&self, mask: u16) -> Board {
+        let mut board = self.view.clone();
+        for (i, &(pos, o)) in self.unknown.iter().enumerate() {
+            let ghost = if mask & (1 << i) != 0 {
+                Ghost::Red
+            } else {
+                Ghost::Blue
+            };
+            board.set_cell(pos, Cell::owned(ghost, o.owner(), o.id()));
+        }
+        board
+    }
+    /// All boards consistent with the view
+    pub fn enumerate(&self) -> impl Iterator<Item = Board> + '_ {
+        self.assignments.iter().map(move |&mask| self.board(mask))
+    }
+    /// Sample a board consistent with the view.
+    /// `prior[id]` is the probability that the ghost `id` is red. If it's `None` or
+    /// gives no weight to any consistent assignment, all of them are equally likely.
+    pub fn sample(&self, rng: &mut RngHandle, prior: Option<&[f64; 8]>) -> Board {
+        let uniform = |rng: &mut RngHandle| {
+            let idx = rng.range(0..self.assignments.len());
+            self.board(self.assignments[idx])
+        };
+        let prior = match prior {
+            Some(p) => p,
+            None => return uniform(rng),
+        };
+        let weight = |mask: u16| -> f64 {
+            self.unknown
+                .iter()
+                .enumerate()
+                .map(|(i, (_, o))| {
+                    let p = prior[usize::from(o.id().as_u8())];
+                    if mask & (1 << i) != 0 {
+                        p
+                    } else {
+                        1.0 - p
+                    }
+                })
+                .product()
+        };
+        let weights: Vec<_> = self.assignments.iter().map(|&m| weight(m)).collect();
+        let sum: f64 = weights.iter().sum();
+        if !(sum > 0.0 && sum.is_finite()) {
+            return uniform(rng);
+        }
+        let mut r = rng.gen::<f64>() * sum;
+        for (&mask, w) in self.assignments.iter().zip(weights) {
+            if r < w {
+                return self.board(mask);
+            }
+            r -= w;
+        }
+        self.board(*self.assignments.last().unwrap())
+    }
+}
+
+#[test]
+fn determinize_initial() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let view = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let det = Determinizer::new(&view, PlayerID::P1, view.captured()).unwrap();
+    assert_eq!(det.len(), 70);
+    let boards: Vec<_> = det.enumerate().collect();
+    for board in &boards {
+        let reds = Board::iter()
+            .into_iter()
+            .filter(|&(x, y)| match board[Position::new(x, y)] {
+                Cell::Owned(o) => o.owner() == PlayerID::P2 && o.ghost() == Ghost::Red,
+                Cell::Empty => false,
+            })
+            .count();
+        assert_eq!(reds, 4);
+        assert!(board.view_for(PlayerID::P1) == view);
+    }
+    let mut rng = RngHandle::from_seed(0);
+    assert!(boards.contains(&det.sample(&mut rng, None)));
+    // ghosts A, B, C, D are surely red
+    let prior = [1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+    let board = det.sample(&mut rng, Some(&prior));
+    for (x, y) in Board::iter() {
+        if let Cell::Owned(o) = board[Position::new(x, y)] {
+            if o.owner() == PlayerID::P2 {
+                assert_eq!(o.ghost() == Ghost::Red, o.id().as_u8() < 4);
+            }
+        }
+    }
+    // five ghosts surely red is impossible, so any consistent board is fine
+    let prior = [1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0];
+    let samples: Vec<_> = (0..20).map(|_| det.sample(&mut rng, Some(&prior))).collect();
+    assert!(samples.iter().all(|b| boards.contains(b)));
+    assert!(samples.iter().any(|b| b != &samples[0]));
+}
+
+#[test]
+fn determinize_known_color() {
+    // P2's ghost A is known to be red, the other seven are unknown
+    let fen = "1HrFrDrBr1/1GbEbCbAb1/6/6/1arc?e?g?1/1b?d?f?h?1 1 000/000 b300 0";
+    let view = Board::from_fen(fen).unwrap();
+    let det = Determinizer::new(&view, PlayerID::P1, view.captured()).unwrap();
+    // choose 3 red ghosts out of 7
+    assert_eq!(det.len(), 35);
+    for board in det.enumerate() {
+        assert_eq!(board.count(PlayerID::P2, Ghost::Red), 4);
+        assert_eq!(board.count(PlayerID::P2, Ghost::Blue), 4);
+    }
+}
@@ -1,5 +1,3 @@
-use clap;
-use geister_ai;
 use geister_core::player::*;
 use geister_gpw_proto::run_client;
 use std::net::IpAddr;
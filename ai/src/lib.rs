@@ -1,12 +1,13 @@
 #[macro_use]
 extern crate derive_more;
-use clap;
-mod rnghandle;
-mod yowagoshi;
-mod metadata;
+mod determinize;
 mod random;
+pub mod rnghandle;
+mod yowagoshi;
 
+pub use self::determinize::Determinizer;
 pub use self::random::RandomAi;
+pub use self::rnghandle::RngHandle;
 pub use self::yowagoshi::Player as YowagoshiAi;
 
 pub fn args<'a, 'b>(app: clap::App<'a, 'b>) -> clap::ArgMatches<'a> {
@@ -39,22 +39,17 @@ impl Player for RandomAi {
         &self.board
     }
     fn step(&mut self, board: Board) -> Result<Move, ()> {
-        let mut cand = vec![];
         self.board = board;
-        for (x, y) in Board::iter() {
-            let pos = Position::new(x, y);
-            if self.board[pos].owner().unwrap_or(self.id.rev()) != self.id {
-                continue;
-            }
-            for d in Direction::iter() {
-                let mov = Move { pos, direction: d };
-                match self.board.can_move(mov) {
-                    MoveResult::Err => continue,
-                    MoveResult::Ok => cand.push(mov),
-                    MoveResult::Win => return Ok(mov),
-                }
+        let mut cand = vec![];
+        for (mov, res) in self.board.legal_moves(self.id) {
+            match res {
+                MoveResult::Escape => return Ok(mov),
+                _ => cand.push(mov),
             }
         }
+        if cand.is_empty() {
+            return Err(());
+        }
         let idx = self.rng.range(0..cand.len());
         Ok(cand[idx])
     }
@@ -3,7 +3,6 @@ use rand::{
     distributions::uniform::SampleUniform, thread_rng, Error as RndError, Rng, RngCore, SeedableRng,
 };
 use rand_xorshift::XorShiftRng;
-use std::mem;
 use std::ops::{Bound, Range, RangeBounds};
 
 /// a set implementation using Fenwick Tree
@@ -87,6 +86,10 @@ impl FenwickSet {
     pub fn len(&self) -> usize {
         self.num_elements
     }
+    /// return if the set has no element
+    pub fn is_empty(&self) -> bool {
+        self.num_elements == 0
+    }
     /// select one integer randomly from the set
     pub fn select<R: Rng>(&self, rng: &mut R) -> Option<usize> {
         if self.num_elements == 0 {
@@ -140,7 +143,7 @@ pub struct FwsIter<'a> {
 impl<'a> Iterator for FwsIter<'a> {
     type Item = usize;
     fn next(&mut self) -> Option<usize> {
-        fws_iter_next(&self.fwt, &mut self.current, &mut self.before)
+        fws_iter_next(self.fwt, &mut self.current, &mut self.before)
     }
 }
 
@@ -194,7 +197,7 @@ impl FenwickTree {
     fn sum_range(&self, range: Range<usize>) -> i32 {
         let sum1 = self.sum(range.end);
         if range.start == 0 {
-            return sum1;
+            sum1
         } else {
             let sum2 = self.sum(range.start);
             sum1 - sum2
@@ -257,7 +260,7 @@ pub fn gen_seed() -> u128 {
 
 impl RngHandle {
     fn gen_seed(seed: u128) -> [u8; 16] {
-        unsafe { mem::transmute::<_, [u8; 16]>(seed) }
+        seed.to_ne_bytes()
     }
     /// create new Rng by specified seed
     pub fn from_seed(seed: u128) -> Self {
@@ -270,7 +273,7 @@ impl RngHandle {
         RngHandle(XorShiftRng::from_seed(seed))
     }
     /// select some values randomly from given range
-    pub fn select<T: PrimInt>(&mut self, range: impl RangeBounds<T>) -> RandomSelecter<T> {
+    pub fn select<T: PrimInt>(&mut self, range: impl RangeBounds<T>) -> RandomSelecter<'_, T> {
         let range = bounds_to_range(range);
         let width = range.end - range.start;
         let width = width.to_usize().expect("[RngHandle::select] NumCast error");
@@ -284,7 +287,7 @@ impl RngHandle {
         }
     }
     /// select some values randomly using given FenwickSet
-    pub fn select_with<T: PrimInt>(&mut self, set: FenwickSet) -> RandomSelecter<T> {
+    pub fn select_with<T: PrimInt>(&mut self, set: FenwickSet) -> RandomSelecter<'_, T> {
         RandomSelecter {
             offset: T::zero(),
             selected: set,
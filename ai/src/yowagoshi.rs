@@ -1,6 +1,5 @@
 use geister_core::{board::*, player::{PlayerID, Player as PlayerT}};
 use crate::rnghandle::RngHandle;
-use crate::metadata::MetaDataList;
 use crate::random;
 
 
@@ -18,6 +17,7 @@ impl Eval {
             _ => Eval(0),
         }
     }
+    #[allow(clippy::if_same_then_else)]
     fn phase(player: PlayerID, pos: Position) -> Self {
         match player {
             PlayerID::P1 => {
@@ -43,7 +43,6 @@ pub struct Player {
     id: PlayerID,
     board: Board,
     rng: RngHandle,
-    meta: MetaDataList,
 }
 
 impl Player {
@@ -52,7 +51,6 @@ impl Player {
             id,
             board: Board::default(),
             rng: RngHandle::default(),
-            meta: MetaDataList::new(),
         }
     }
 }
@@ -66,10 +64,6 @@ impl SearchError {
     }
 }
 
-fn unimpl() -> SearchError {
-    SearchError::from_str("Unimplemented!")
-}
-
 impl PlayerT for Player {
     type Error = SearchError;
     fn id(&self) -> PlayerID {
@@ -84,53 +78,47 @@ impl PlayerT for Player {
         &self.board
     }
     fn step(&mut self, board: Board) -> Result<Move, Self::Error> {
-        let mut cand = vec![];
-        let diff = self.board.diff(&board);
-        let _transition: Vec<_> = diff.into_iter().map(|x| x.into_transition()).collect();
         self.board = board;
-        for (x, y) in Board::iter() {
-            let pos = Position::new(x, y);
-            if self.board[pos].owner().unwrap_or(self.id.rev()) != self.id {
-                continue;
-            }
-            for d in Direction::iter() {
-                let mov = Move { pos, direction: d };
-                match self.board.can_move(mov) {
-                    MoveResult::Err => continue,
-                    MoveResult::Ok => cand.push(mov),
-                    MoveResult::Win => return Ok(mov),
-                }
+        let mut cand = vec![];
+        for (mov, res) in self.board.legal_moves(self.id) {
+            match res {
+                MoveResult::Escape => return Ok(mov),
+                _ => cand.push((mov, res)),
             }
         }
         let mut evals = vec![];
-        for mov in cand {
-            let mut board = self.board.clone();
-            let t = match board.transit(mov) {
-                Ok(t) => t,
+        for (mov, res) in cand {
+            let undo = match self.board.apply(mov) {
+                Ok(undo) => undo,
                 Err(_) => continue,
             };
             let to = mov.to();
             if to.is_escape(self.id.rev()) {
+                self.board.undo(undo);
                 return Ok(mov);
             }
             let mut eval = Eval::default();
-            if let Transition::Lost(_) = t {
+            if let MoveResult::Capture(_) = res {
                 eval += Eval::lost();
             }
             for d in Direction::iter() {
                 let pos = to + d.to_pos();
                 if pos.is_valid() {
-                    if let Cell::Owned(o) = board[pos] {
+                    if let Cell::Owned(o) = self.board[pos] {
                         if o.owner() == self.id.rev() {
                             eval.0 += Eval::around(o.ghost()).0 * Eval::phase(o.owner(), pos).0;
                         }
                     }
                 }
             }
+            self.board.undo(undo);
             evals.push((mov, eval));
         }
         evals.sort_by_key(|e| e.1);
-        Ok(evals.last().unwrap().0)
+        evals
+            .last()
+            .map(|e| e.0)
+            .ok_or_else(|| SearchError::from_str("No legal move"))
     }
 }
 
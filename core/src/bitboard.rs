@@ -0,0 +1,439 @@
+use crate::board::*;
+use crate::error::ErrorKind;
+use crate::event::GameEvent;
+use crate::outcome::Captured;
+use crate::player::PlayerID;
+use crate::rules::{EscapeRule, RuleSet};
+use std::hash::{Hash, Hasher};
+
+/// Squares are laid out as `x + y * STRIDE`, so that shifting a mask never
+/// wraps a ghost around to the other edge of the board.
+const STRIDE: usize = 8;
+const VALID: u64 = 0x3f3f_3f3f_3f3f;
+const NUM_SQUARES: usize = STRIDE * (BOARD_HEIGHT - 1) + BOARD_WIDTH;
+const RED: usize = 0;
+const BLUE: usize = 1;
+
+const SHIFTS: [(Direction, isize); 4] = [
+    (Direction::Up, -(STRIDE as isize)),
+    (Direction::Down, STRIDE as isize),
+    (Direction::Left, -1),
+    (Direction::Right, 1),
+];
+
+fn owner_index(player: PlayerID) -> usize {
+    match player {
+        PlayerID::P1 => 0,
+        PlayerID::P2 => 1,
+    }
+}
+
+fn square(pos: Position) -> usize {
+    debug_assert!(pos.is_valid());
+    pos.x as usize + pos.y as usize * STRIDE
+}
+
+fn position(sq: usize) -> Position {
+    Position::new((sq % STRIDE) as i8, (sq / STRIDE) as i8)
+}
+
+fn shift(mask: u64, offset: isize) -> u64 {
+    if offset < 0 {
+        mask >> -offset
+    } else {
+        mask << offset
+    }
+}
+
+fn escape_mask(player: PlayerID) -> u64 {
+    let y = match player {
+        PlayerID::P1 => BOARD_HEIGHT - 1,
+        PlayerID::P2 => 0,
+    };
+    let left = 1 << (y * STRIDE);
+    left | left << (BOARD_WIDTH - 1)
+}
+
+/// Iterate over the squares set in `mask`
+fn squares(mut mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let sq = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(sq)
+    })
+}
+
+/// Board representation by bit masks, for fast search.
+/// It follows exactly the same rules as `Board`.
+#[derive(Clone, Copy, Debug)]
+pub struct BitBoard {
+    owners: [u64; 2],
+    /// Red and blue ghosts. Ghosts in neither of them are unknown.
+    colors: [u64; 2],
+    ids: [u8; NUM_SQUARES],
+    next: PlayerID,
+    captured: Captured,
+    rules: RuleSet,
+    /// Number of moves played, not compared by `Eq` nor hashed
+    plies: u32,
+}
+
+impl PartialEq for BitBoard {
+    fn eq(&self, other: &Self) -> bool {
+        self.owners == other.owners
+            && self.colors == other.colors
+            && self.ids == other.ids
+            && self.next == other.next
+            && self.captured == other.captured
+            && self.rules == other.rules
+    }
+}
+
+impl Eq for BitBoard {}
+
+impl Hash for BitBoard {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.owners.hash(state);
+        self.colors.hash(state);
+        self.ids.hash(state);
+        self.next.hash(state);
+        self.captured.hash(state);
+        self.rules.hash(state);
+    }
+}
+
+impl Default for BitBoard {
+    fn default() -> Self {
+        BitBoard {
+            owners: [0; 2],
+            colors: [0; 2],
+            ids: [0; NUM_SQUARES],
+            next: PlayerID::P1,
+            captured: Captured::default(),
+            rules: RuleSet::default(),
+            plies: 0,
+        }
+    }
+}
+
+impl BitBoard {
+    /// Squares occupied by the ghosts of `player`
+    pub fn owned(&self, player: PlayerID) -> u64 {
+        self.owners[owner_index(player)]
+    }
+    /// Squares occupied by the ghosts of `color`
+    pub fn colored(&self, color: Ghost) -> u64 {
+        match color {
+            Ghost::Red => self.colors[RED],
+            Ghost::Blue => self.colors[BLUE],
+            Ghost::Unknown => {
+                (self.owners[0] | self.owners[1]) & !(self.colors[RED] | self.colors[BLUE])
+            }
+        }
+    }
+    pub fn next(&self) -> PlayerID {
+        self.next
+    }
+    pub fn set_next(&mut self, player: PlayerID) {
+        self.next = player;
+    }
+    pub fn captured(&self) -> &Captured {
+        &self.captured
+    }
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+    /// Number of moves played
+    pub fn plies(&self) -> u32 {
+        self.plies
+    }
+    pub fn set_plies(&mut self, plies: u32) {
+        self.plies = plies;
+    }
+    /// Same as `Board::remaining_plies`
+    pub fn remaining_plies(&self) -> Option<u32> {
+        self.rules.turn_limit.map(|limit| limit.saturating_sub(self.plies))
+    }
+    /// Same as `Board::is_turn_limit`
+    pub fn is_turn_limit(&self) -> bool {
+        self.remaining_plies() == Some(0)
+    }
+    pub fn get(&self, pos: Position) -> Cell {
+        self.cell(square(pos))
+    }
+    fn cell(&self, sq: usize) -> Cell {
+        let bit = 1 << sq;
+        let owner = if self.owners[0] & bit != 0 {
+            PlayerID::P1
+        } else if self.owners[1] & bit != 0 {
+            PlayerID::P2
+        } else {
+            return Cell::Empty;
+        };
+        let ghost = if self.colors[RED] & bit != 0 {
+            Ghost::Red
+        } else if self.colors[BLUE] & bit != 0 {
+            Ghost::Blue
+        } else {
+            Ghost::Unknown
+        };
+        Cell::owned(ghost, owner, GhostID::from_u8(self.ids[sq]).unwrap())
+    }
+    fn put(&mut self, sq: usize, cell: OwnedCell) {
+        let bit = 1 << sq;
+        self.owners[owner_index(cell.owner())] |= bit;
+        match cell.ghost() {
+            Ghost::Red => self.colors[RED] |= bit,
+            Ghost::Blue => self.colors[BLUE] |= bit,
+            Ghost::Unknown => {}
+        }
+        self.ids[sq] = cell.id().as_u8();
+    }
+    fn remove(&mut self, sq: usize) {
+        let mask = !(1 << sq);
+        self.owners[0] &= mask;
+        self.owners[1] &= mask;
+        self.colors[RED] &= mask;
+        self.colors[BLUE] &= mask;
+        self.ids[sq] = 0;
+    }
+    fn owned_cell(&self, sq: usize) -> Option<OwnedCell> {
+        match self.cell(sq) {
+            Cell::Owned(o) => Some(o),
+            Cell::Empty => None,
+        }
+    }
+    fn validate(&self, mov: Move) -> Result<(OwnedCell, Target), ErrorKind> {
+        if !mov.pos.is_valid() {
+            return ErrorKind::OffBoard(mov).into();
+        }
+        let from = match self.owned_cell(square(mov.pos)) {
+            Some(o) => o,
+            None => return ErrorKind::EmptySource(mov.pos).into(),
+        };
+        let to = mov.to();
+        if !to.is_valid() {
+            if !mov.can_escape(from.owner()) {
+                return ErrorKind::OffBoard(mov).into();
+            }
+            if !self.rules.can_escape(from.ghost()) {
+                return ErrorKind::CannotEscape(mov).into();
+            }
+            return Ok((from, Target::Escape));
+        }
+        match self.owned_cell(square(to)) {
+            Some(o) if o.owner() == from.owner() => ErrorKind::FriendlyCapture(mov).into(),
+            Some(o) => Ok((from, Target::Capture(o))),
+            None => Ok((from, Target::Empty)),
+        }
+    }
+    pub fn can_move(&self, mov: Move) -> MoveResult {
+        MoveResult::new(self.validate(mov))
+    }
+    /// Same as `Board::legal_moves`, but the order of moves can differ.
+    pub fn legal_moves(&self, player: PlayerID) -> Vec<(Move, MoveResult)> {
+        let mut buf = Vec::with_capacity(32);
+        self.legal_moves_into(player, &mut buf);
+        buf
+    }
+    pub fn legal_moves_into(&self, player: PlayerID, buf: &mut Vec<(Move, MoveResult)>) {
+        buf.clear();
+        let own = self.owned(player);
+        let opponent = self.owned(player.rev());
+        let targets = VALID & !own;
+        for &(direction, offset) in &SHIFTS {
+            for to in squares(shift(own, offset) & targets) {
+                let from = (to as isize - offset) as usize;
+                let mov = Move {
+                    pos: position(from),
+                    direction,
+                };
+                let res = if opponent & 1 << to != 0 {
+                    MoveResult::Capture(self.owned_cell(to).unwrap())
+                } else {
+                    MoveResult::Quiet
+                };
+                buf.push((mov, res));
+            }
+        }
+        let escapers = match self.rules.escape {
+            EscapeRule::BlueOnly => own & self.colors[BLUE],
+            EscapeRule::AnyColor => own,
+        };
+        for from in squares(escapers & escape_mask(player)) {
+            let pos = position(from);
+            let direction = if pos.x == 0 {
+                Direction::Left
+            } else {
+                Direction::Right
+            };
+            buf.push((Move { pos, direction }, MoveResult::Escape));
+        }
+    }
+    /// Apply `mov` of the next player in O(1).
+    pub fn apply(&mut self, mov: Move) -> Result<Undo, ErrorKind> {
+        let (moved, target) = self.validate(mov)?;
+        if moved.owner() != self.next {
+            return ErrorKind::NotYourPiece(mov).into();
+        }
+        let mut undo = Undo {
+            mov,
+            moved,
+            captured: None,
+            escaped: false,
+        };
+        self.remove(square(mov.pos));
+        self.next = self.next.rev();
+        self.plies = self.plies.saturating_add(1);
+        match target {
+            Target::Escape => {
+                undo.escaped = true;
+                return Ok(undo);
+            }
+            Target::Capture(o) => {
+                self.remove(square(mov.to()));
+                self.captured.add(o);
+                undo.captured = Some(o);
+            }
+            Target::Empty => {}
+        }
+        self.put(square(mov.to()), moved);
+        Ok(undo)
+    }
+    /// Take back a move applied by `apply`.
+    pub fn undo(&mut self, undo: Undo) {
+        let Undo {
+            mov,
+            moved,
+            captured,
+            escaped,
+        } = undo;
+        if !escaped {
+            self.remove(square(mov.to()));
+        }
+        if let Some(o) = captured {
+            self.put(square(mov.to()), o);
+            self.captured.remove(o);
+        }
+        self.put(square(mov.pos), moved);
+        self.next = moved.owner();
+        self.plies = self.plies.saturating_sub(1);
+    }
+    /// Same as `Board::transit`
+    pub fn transit(&mut self, mov: Move) -> Result<GameEvent, ErrorKind> {
+        self.apply(mov).map(|undo| undo.event())
+    }
+}
+
+impl<'a, C: AsCell> From<&'a GenericBoard<C>> for BitBoard {
+    fn from(board: &'a GenericBoard<C>) -> Self {
+        let mut res = BitBoard::default();
+        for (x, y) in GenericBoard::<C>::iter() {
+            let pos = Position::new(x, y);
+            if let Cell::Owned(o) = board[pos].to_cell() {
+                res.put(square(pos), o);
+            }
+        }
+        res.next = board.next();
+        res.captured = *board.captured();
+        res.rules = *board.rules();
+        res.plies = board.plies();
+        res
+    }
+}
+
+impl<'a> From<&'a BitBoard> for Board {
+    fn from(bitboard: &'a BitBoard) -> Self {
+        let mut board = Board::default();
+        for sq in squares(bitboard.owners[0] | bitboard.owners[1]) {
+            board.set(position(sq), bitboard.cell(sq));
+        }
+        board.set_next(bitboard.next);
+        *board.captured_mut() = bitboard.captured;
+        board.set_rules(bitboard.rules);
+        board.set_plies(bitboard.plies);
+        board
+    }
+}
+
+#[test]
+fn same_rules_as_board() {
+    let p1_red = [
+        Position::new(1, 0),
+        Position::new(2, 1),
+        Position::new(3, 0),
+        Position::new(4, 1),
+    ];
+    let p2_red = [
+        Position::new(1, 4),
+        Position::new(2, 4),
+        Position::new(3, 5),
+        Position::new(4, 5),
+    ];
+    let start = crate::setup::SetupBuilder::new()
+        .red(PlayerID::P1, &p1_red)
+        .red(PlayerID::P2, &p2_red)
+        .build()
+        .unwrap();
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut rand = move |n: usize| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed as usize % n
+    };
+    // plies survive both conversions
+    let mut late = start.clone();
+    late.set_plies(250);
+    assert_eq!(BitBoard::from(&late).remaining_plies(), Some(50));
+    assert_eq!(Board::from(&BitBoard::from(&late)).plies(), 250);
+    let key = |&(m, _): &(Move, MoveResult)| (m.pos.x, m.pos.y, m.direction);
+    for _ in 0..20 {
+        let mut board = start.clone();
+        let mut bitboard = BitBoard::from(&board);
+        let (mut buf, mut bitbuf) = (vec![], vec![]);
+        for _ in 0..200 {
+            board.legal_moves_into(board.next(), &mut buf);
+            bitboard.legal_moves_into(bitboard.next(), &mut bitbuf);
+            buf.sort_by_key(key);
+            bitbuf.sort_by_key(key);
+            assert_eq!(buf, bitbuf);
+            // illegal moves are rejected in the same way
+            for (x, y) in Board::iter() {
+                for direction in Direction::iter() {
+                    let mov = Move {
+                        pos: Position::new(x, y),
+                        direction,
+                    };
+                    assert_eq!(board.clone().transit(mov), bitboard.clone().transit(mov));
+                }
+            }
+            if buf.is_empty() {
+                break;
+            }
+            let mov = buf[rand(buf.len())].0;
+            let before = bitboard;
+            let undo = bitboard.apply(mov).unwrap();
+            let mut undone = bitboard;
+            undone.undo(undo);
+            assert_eq!(undone, before);
+            assert_eq!(undone.plies(), before.plies());
+            let event = board.transit(mov).unwrap();
+            assert_eq!(BitBoard::from(&board), bitboard);
+            assert_eq!(bitboard.plies(), board.plies());
+            assert_eq!(bitboard.remaining_plies(), board.remaining_plies());
+            assert_eq!(bitboard.is_turn_limit(), board.is_turn_limit());
+            assert_eq!(Board::from(&bitboard).diff(&board).len(), 0);
+            assert_eq!(Board::from(&bitboard).plies(), board.plies());
+            if let GameEvent::Escaped { .. } = event {
+                break;
+            }
+            if board.captured().outcome().is_some() {
+                break;
+            }
+        }
+    }
+}
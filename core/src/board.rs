@@ -1,7 +1,14 @@
-use crate::error::ErrorKind;
+use crate::error::{ErrorKind, ParseError};
+use crate::event::GameEvent;
+use crate::outcome::Captured;
+use crate::render::Renderer;
+use crate::rules::{EscapeRule, RuleSet};
+use crate::setup::check_red;
+use crate::zobrist;
 use std::fmt;
-use std::ops::{Index, IndexMut};
-use yansi::Paint;
+use std::hash::{Hash, Hasher};
+use std::ops::Index;
+use std::str::FromStr;
 pub const BOARD_HEIGHT: usize = 6;
 pub const BOARD_WIDTH: usize = 6;
 use crate::player::PlayerID;
@@ -14,8 +21,9 @@ fn check(n: i8, max: usize) -> bool {
     (n as usize) < max
 }
 
-#[derive(Clone, Copy, Debug, Display, Eq, PartialEq, Hash, Add, Sub)]
-#[display(fmt = "({}, {})", x, y)]
+/// Displayed as `b2`: the column from `a` and the row from `1`
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Add, Sub)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub x: i8,
     pub y: i8,
@@ -28,9 +36,9 @@ impl Position {
         if !(1 <= x && x < BOARD_WIDTH as i8 - 1) {
             return None;
         }
-        if 0 <= y && y < 2 {
+        if (0..2).contains(&y) {
             Some(PlayerID::P1)
-        } else if BOARD_HEIGHT as i8 - 2 <= y && y < BOARD_HEIGHT as i8 {
+        } else if (BOARD_HEIGHT as i8 - 2..BOARD_HEIGHT as i8).contains(&y) {
             Some(PlayerID::P2)
         } else {
             None
@@ -49,6 +57,14 @@ impl Position {
         }
         x as usize + y as usize * BOARD_WIDTH
     }
+    /// Rotate by 180 degrees.
+    pub fn flipped(self) -> Self {
+        Position::new(BOARD_WIDTH as i8 - 1 - self.x, BOARD_HEIGHT as i8 - 1 - self.y)
+    }
+    /// Reflect horizontally.
+    pub fn mirrored(self) -> Self {
+        Position::new(BOARD_WIDTH as i8 - 1 - self.x, self.y)
+    }
     pub fn is_escape(&self, player: PlayerID) -> bool {
         let &Position { x, y } = self;
         if x != 0 && x != BOARD_WIDTH as i8 - 1 {
@@ -67,7 +83,35 @@ impl From<(i8, i8)> for Position {
     }
 }
 
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_valid() {
+            write!(f, "{}{}", (b'a' + self.x as u8) as char, self.y + 1)
+        } else {
+            write!(f, "({}, {})", self.x, self.y)
+        }
+    }
+}
+
+impl FromStr for Position {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let err = || ParseError::Position(s.to_owned());
+        let b = s.as_bytes();
+        if b.len() != 2 {
+            return Err(err());
+        }
+        let pos = Position::new(b[0].wrapping_sub(b'a') as i8, b[1].wrapping_sub(b'1') as i8);
+        if pos.is_valid() {
+            Ok(pos)
+        } else {
+            Err(err())
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Hash, PartialEq, PartialOrd, Ord, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Up,
     Down,
@@ -91,7 +135,7 @@ impl Direction {
             Direction::Left,
             Direction::Right,
         ];
-        ITER.iter().map(|&x| x)
+        ITER.iter().copied()
     }
     pub fn rev(self) -> Self {
         match self {
@@ -101,20 +145,72 @@ impl Direction {
             Direction::Right => Direction::Left,
         }
     }
-    pub fn is_ordinal(&self) -> bool {
+    /// Reflect horizontally.
+    pub fn mirrored(self) -> Self {
         match self {
-            Direction::Up | Direction::Down => false,
-            _ => true,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            d => d,
+        }
+    }
+    pub fn is_ordinal(&self) -> bool {
+        !matches!(self, Direction::Up | Direction::Down)
+    }
+}
+
+/// Displayed as `N`, `S`, `W` or `E`, where `N` is `Up`
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = match self {
+            Direction::Up => 'N',
+            Direction::Down => 'S',
+            Direction::Left => 'W',
+            Direction::Right => 'E',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+impl FromStr for Direction {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        match s {
+            "N" => Ok(Direction::Up),
+            "S" => Ok(Direction::Down),
+            "W" => Ok(Direction::Left),
+            "E" => Ok(Direction::Right),
+            _ => Err(ParseError::Direction(s.to_owned())),
         }
     }
 }
 
+/// Displayed as `b2N`: the position and the direction
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Move {
     pub pos: Position,
     pub direction: Direction,
 }
 
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.pos, self.direction)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let err = || ParseError::Move(s.to_owned());
+        if s.len() != 3 || !s.is_char_boundary(2) {
+            return Err(err());
+        }
+        let pos = s[..2].parse().map_err(|_| err())?;
+        let direction = s[2..].parse().map_err(|_| err())?;
+        Ok(Move { pos, direction })
+    }
+}
+
 impl Move {
     pub fn to(self) -> Position {
         let Move { pos, direction } = self;
@@ -126,9 +222,32 @@ impl Move {
     pub fn can_escape(&self, player: PlayerID) -> bool {
         self.pos.is_escape(player) && self.direction.is_ordinal()
     }
+    /// The same move on the board rotated by 180 degrees
+    pub fn flipped(self) -> Self {
+        Move {
+            pos: self.pos.flipped(),
+            direction: self.direction.rev(),
+        }
+    }
+    /// The same move on the horizontally reflected board
+    pub fn mirrored(self) -> Self {
+        Move {
+            pos: self.pos.mirrored(),
+            direction: self.direction.mirrored(),
+        }
+    }
+    /// Convert a move between absolute coordinates and the perspective of `player`.
+    /// See `GenericBoard::perspective`.
+    pub fn perspective(self, player: PlayerID) -> Self {
+        match player {
+            PlayerID::P1 => self.flipped(),
+            PlayerID::P2 => self,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GhostID {
     A,
     B,
@@ -174,16 +293,29 @@ impl fmt::Display for GhostID {
     }
 }
 
+impl FromStr for GhostID {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        match s.as_bytes() {
+            &[c] if (b'A'..=b'H').contains(&c) => Ok(GhostID::from_u8(c - b'A').unwrap()),
+            _ => Err(ParseError::GhostID(s.to_owned())),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Ghost {
     Unknown,
     Red,
     Blue,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Cell {
     Owned(OwnedCell),
+    #[default]
     Empty,
 }
 
@@ -210,17 +342,53 @@ impl Cell {
             Cell::Empty => None,
         }
     }
-}
-
-impl Default for Cell {
-    fn default() -> Self {
-        Cell::Empty
+    /// Swap the owner
+    pub fn flipped(self) -> Self {
+        match self {
+            Cell::Owned(o) => Cell::Owned(o.flipped()),
+            Cell::Empty => Cell::Empty,
+        }
     }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "OwnedCellRepr", into = "OwnedCellRepr")
+)]
 pub struct OwnedCell(u8);
 
+/// Readable form of `OwnedCell` for serialization
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+struct OwnedCellRepr {
+    owner: PlayerID,
+    ghost: Ghost,
+    id: GhostID,
+}
+
+#[cfg(feature = "serde")]
+impl From<OwnedCellRepr> for OwnedCell {
+    fn from(r: OwnedCellRepr) -> Self {
+        match Cell::owned(r.ghost, r.owner, r.id) {
+            Cell::Owned(o) => o,
+            Cell::Empty => unreachable!(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<OwnedCell> for OwnedCellRepr {
+    fn from(o: OwnedCell) -> Self {
+        OwnedCellRepr {
+            owner: o.owner(),
+            ghost: o.ghost(),
+            id: o.id(),
+        }
+    }
+}
+
 impl OwnedCell {
     const GHOST_MASK: u8 = 0b00000011;
     const OWNER_MASK: u8 = 0b00000100;
@@ -269,23 +437,27 @@ impl OwnedCell {
     pub fn id(&self) -> GhostID {
         GhostID::from_u8(self.get_mask(Self::ID_MASK, Self::ID_OFFSET)).unwrap()
     }
+    /// Swap the owner
+    pub fn flipped(self) -> Self {
+        OwnedCell(self.0 ^ Self::OWNER_MASK)
+    }
 }
 
+/// Displayed as the ID and the color, e.g. `Hr` for P1's red ghost H and `a?` for
+/// P2's unknown ghost A
 impl fmt::Display for OwnedCell {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let (kind, owner) = (self.ghost(), self.owner());
-        match owner {
-            PlayerID::P1 => match kind {
-                Ghost::Red => write!(f, "{}", Paint::red("▽")),
-                Ghost::Blue => write!(f, "{}", Paint::blue("▽")),
-                Ghost::Unknown => write!(f, "{}", Paint::white("▽")),
-            },
-            PlayerID::P2 => match kind {
-                Ghost::Red => write!(f, "{}", Paint::red("△")),
-                Ghost::Blue => write!(f, "{}", Paint::blue("△")),
-                Ghost::Unknown => write!(f, "{}", Paint::white("△")),
-            },
-        }
+        let id = (b'A' + self.id().as_u8()) as char;
+        let id = match self.owner() {
+            PlayerID::P1 => id,
+            PlayerID::P2 => id.to_ascii_lowercase(),
+        };
+        let color = match self.ghost() {
+            Ghost::Red => 'r',
+            Ghost::Blue => 'b',
+            Ghost::Unknown => '?',
+        };
+        write!(f, "{}{}", id, color)
     }
 }
 
@@ -293,7 +465,7 @@ impl fmt::Display for Cell {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Cell::Owned(cell) => write!(f, "{}", cell),
-            Cell::Empty => write!(f, "  "),
+            Cell::Empty => write!(f, ".."),
         }
     }
 }
@@ -318,147 +490,487 @@ impl AsCell for Cell {
 
 #[derive(Clone)]
 pub struct GenericBoard<C: Sized> {
-    inner: [C; BOARD_HEIGHT * BOARD_WIDTH],    
+    inner: [C; BOARD_HEIGHT * BOARD_WIDTH],
+    next: PlayerID,
+    captured: Captured,
+    rules: RuleSet,
+    /// Number of moves played, not compared by `Eq` nor hashed
+    plies: u32,
+    /// Zobrist hash of ghosts and the player to move
+    hash: u64,
 }
 
 pub type Board = GenericBoard<Cell>;
     
+impl<C: AsCell> PartialEq for GenericBoard<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash
+            && self.next == other.next
+            && self.captured == other.captured
+            && self.rules == other.rules
+            && self
+                .inner
+                .iter()
+                .zip(other.inner.iter())
+                .all(|(a, b)| a.to_cell() == b.to_cell())
+    }
+}
+
+impl<C: AsCell> Eq for GenericBoard<C> {}
+
+impl<C: AsCell> Hash for GenericBoard<C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
 impl<C: AsCell> Default for GenericBoard<C> {
     fn default() -> Self {
         GenericBoard {
             inner: [C::default(); BOARD_HEIGHT * BOARD_WIDTH],
+            next: PlayerID::P1,
+            captured: Captured::default(),
+            rules: RuleSet::default(),
+            plies: 0,
+            hash: 0,
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// What a move would do, without applying it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MoveResult {
-    Ok,
-    Win,
-    Err,
+    /// Moving to an empty square
+    Quiet,
+    /// Capturing the ghost
+    Capture(OwnedCell),
+    /// Leaving the board, which wins the game
+    Escape,
+    Illegal(ErrorKind),
 }
 
-impl From<bool> for MoveResult {
-    fn from(b: bool) -> Self {
-        if b {
-            MoveResult::Ok
-        } else {
-            MoveResult::Err
+impl MoveResult {
+    pub(crate) fn new(res: Result<(OwnedCell, Target), ErrorKind>) -> Self {
+        match res {
+            Ok((_, Target::Empty)) => MoveResult::Quiet,
+            Ok((_, Target::Capture(o))) => MoveResult::Capture(o),
+            Ok((_, Target::Escape)) => MoveResult::Escape,
+            Err(e) => MoveResult::Illegal(e),
         }
     }
+    pub fn is_legal(&self) -> bool {
+        !matches!(self, MoveResult::Illegal(_))
+    }
 }
-    
+
+/// A cell which differs between two boards.
+/// Use `GenericBoard::infer_event` for what the move between them did.
 pub struct Diff {
     pub pos: Position,
     pub before: Cell,
     pub after: Cell,
 }
 
-impl Diff {
-    pub fn into_transition(self) -> Transition {
-        let Diff {
-            pos,
-            before,
-            after,
-        } = self;
-        match before {
-            Cell::Owned(before) => match after {
-                Cell::Owned(_) => Transition::Lost(before),
-                Cell::Empty => {
-                    let owner = before.owner();
-                    if before.ghost() == Ghost::Blue && pos.is_escape(owner) {
-                        Transition::End(owner)
-                    } else {
-                        Transition::None
-                    }
-                }
+/// What is needed to take back a move applied by `apply`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Undo {
+    pub mov: Move,
+    pub moved: OwnedCell,
+    pub captured: Option<OwnedCell>,
+    pub escaped: bool,
+}
+
+impl Undo {
+    /// What the move did
+    pub fn event(&self) -> GameEvent {
+        let Undo {
+            mov,
+            moved,
+            captured,
+            escaped,
+        } = *self;
+        if escaped {
+            GameEvent::Escaped {
+                ghost: moved,
+                pos: mov.pos,
+            }
+        } else if let Some(victim) = captured {
+            GameEvent::Captured {
+                by: moved,
+                victim,
+                pos: mov.to(),
+            }
+        } else {
+            GameEvent::Moved {
+                from: mov.pos,
+                to: mov.to(),
+                ghost: moved,
             }
-            Cell::Empty => Transition::None,
         }
     }
 }
 
+/// What happens at the destination of a legal move
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub enum Transition {
-    Lost(OwnedCell),
-    End(PlayerID),
-    None,
+pub(crate) enum Target {
+    Empty,
+    Capture(OwnedCell),
+    Escape,
 }
+
     
 impl<C: AsCell> GenericBoard<C> {
     pub fn iter() -> RectRange<i8> {
         RectRange::zero_start(BOARD_WIDTH as i8, BOARD_HEIGHT as i8).unwrap()
     }
-    pub fn init_for_player(red_pos: [Position; 4], player: PlayerID) -> Option<Self> {
+    /// The starting board as `player` sees it. Use `setup::SetupBuilder` for both sides.
+    pub fn init_for_player(red_pos: [Position; 4], player: PlayerID) -> Result<Self, ErrorKind> {
+        check_red(player, &red_pos)?;
         let mut board = Self::default();
-        let mut cnt = 0;
         player.init(|pos, id| {
             let ghost = if red_pos.contains(&pos) {
-                cnt += 1;
                 Ghost::Red
             } else {
                 Ghost::Blue
             };
-            *board[pos].as_cell_mut() = Cell::owned(ghost, player, id);
+            board.set_cell(pos, Cell::owned(ghost, player, id));
         });
-        if cnt != 4 {
-            return None;
-        }
         player.rev().init(|pos, id| {
-            *board[pos].as_cell_mut() = Cell::owned(Ghost::Unknown, player.rev(), id);
+            board.set_cell(pos, Cell::owned(Ghost::Unknown, player.rev(), id));
         });
-        Some(board)
+        Ok(board)
+    }
+    /// The player to move next
+    pub fn next(&self) -> PlayerID {
+        self.next
+    }
+    pub fn set_next(&mut self, player: PlayerID) {
+        if player != self.next {
+            self.hash ^= zobrist::SIDE;
+        }
+        self.next = player;
+    }
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
     }
-    pub fn transit(&mut self, mov: Move) -> Result<Transition, ErrorKind> {
+    pub fn set_rules(&mut self, rules: RuleSet) {
+        self.rules = rules;
+    }
+    /// Number of moves played
+    pub fn plies(&self) -> u32 {
+        self.plies
+    }
+    pub fn set_plies(&mut self, plies: u32) {
+        self.plies = plies;
+    }
+    /// How many moves can be played before the turn limit. `None` if there's no limit.
+    pub fn remaining_plies(&self) -> Option<u32> {
+        self.rules.turn_limit.map(|limit| limit.saturating_sub(self.plies))
+    }
+    /// The game is a draw if this is true and nobody has won.
+    pub fn is_turn_limit(&self) -> bool {
+        self.remaining_plies() == Some(0)
+    }
+    /// Zobrist hash of the position, kept up to date by every update
+    pub fn zobrist(&self) -> u64 {
+        self.hash
+    }
+    fn cell_key(idx: usize, cell: Cell) -> u64 {
+        match cell {
+            Cell::Owned(o) => zobrist::key(idx, o.0),
+            Cell::Empty => 0,
+        }
+    }
+    /// Put `cell` on `pos`.
+    pub fn set(&mut self, pos: Position, cell: C) {
+        assert!(pos.is_valid());
+        let idx = pos.to_index();
+        let before = self.inner[idx].to_cell();
+        self.hash ^= Self::cell_key(idx, before) ^ Self::cell_key(idx, cell.to_cell());
+        self.inner[idx] = cell;
+    }
+    /// Replace the `Cell` part of the cell on `pos`.
+    pub fn set_cell(&mut self, pos: Position, cell: Cell) {
+        assert!(pos.is_valid());
+        self.replace(pos.to_index(), cell);
+    }
+    fn replace(&mut self, idx: usize, cell: Cell) {
+        let before = self.inner[idx].as_cell_mut();
+        self.hash ^= Self::cell_key(idx, *before) ^ Self::cell_key(idx, cell);
+        *before = cell;
+    }
+    /// Check `mov` against the rules, regardless of which player is to move.
+    fn validate(&self, mov: Move) -> Result<(OwnedCell, Target), ErrorKind> {
         if !mov.pos.is_valid() {
-            return ErrorKind::InvalidMove(mov).into();
+            return ErrorKind::OffBoard(mov).into();
         }
-        let (from, to) = mov.to_indices();
-        let from = match self.inner[from].to_cell() {
+        let from = match self.inner[mov.pos.to_index()].to_cell() {
             Cell::Owned(o) => o,
-            Cell::Empty => return ErrorKind::InvalidMove(mov).into(),
+            Cell::Empty => return ErrorKind::EmptySource(mov.pos).into(),
         };
-        match self.inner.get_mut(to) {
-            Some(cell) => {
-                let before = cell.to_cell();
-                *cell.as_cell_mut() = Cell::Owned(from);
-                match before {
-                    Cell::Owned(o) => Ok(Transition::Lost(o)),
-                    Cell::Empty => Ok(Transition::None),
+        let to = mov.to();
+        if !to.is_valid() {
+            if !mov.can_escape(from.owner()) {
+                return ErrorKind::OffBoard(mov).into();
+            }
+            if !self.rules.can_escape(from.ghost()) {
+                return ErrorKind::CannotEscape(mov).into();
+            }
+            return Ok((from, Target::Escape));
+        }
+        match self.inner[to.to_index()].to_cell() {
+            Cell::Owned(o) if o.owner() == from.owner() => ErrorKind::FriendlyCapture(mov).into(),
+            Cell::Owned(o) => Ok((from, Target::Capture(o))),
+            Cell::Empty => Ok((from, Target::Empty)),
+        }
+    }
+    /// Apply `mov` of the next player.
+    pub fn transit(&mut self, mov: Move) -> Result<GameEvent, ErrorKind> {
+        self.transit_by(self.next, mov)
+    }
+    /// Apply `mov` of `player`, checking that `player` is to move.
+    pub fn transit_by(&mut self, player: PlayerID, mov: Move) -> Result<GameEvent, ErrorKind> {
+        if player != self.next {
+            return ErrorKind::WrongPlayer(player).into();
+        }
+        self.apply(mov).map(|undo| undo.event())
+    }
+    /// Apply `mov` of the next player and return what `undo` needs to take it back.
+    pub fn apply(&mut self, mov: Move) -> Result<Undo, ErrorKind> {
+        let (moved, target) = self.validate(mov)?;
+        if moved.owner() != self.next {
+            return ErrorKind::NotYourPiece(mov).into();
+        }
+        let mut undo = Undo {
+            mov,
+            moved,
+            captured: None,
+            escaped: false,
+        };
+        self.replace(mov.pos.to_index(), Cell::Empty);
+        self.set_next(self.next.rev());
+        self.plies = self.plies.saturating_add(1);
+        match target {
+            Target::Escape => {
+                undo.escaped = true;
+                return Ok(undo);
+            }
+            Target::Capture(o) => {
+                self.captured.add(o);
+                undo.captured = Some(o);
+            }
+            Target::Empty => {}
+        }
+        self.replace(mov.to().to_index(), Cell::Owned(moved));
+        Ok(undo)
+    }
+    /// Take back a move applied by `apply`.
+    pub fn undo(&mut self, undo: Undo) {
+        let Undo {
+            mov,
+            moved,
+            captured,
+            escaped,
+        } = undo;
+        if !escaped {
+            let cell = match captured {
+                Some(o) => {
+                    self.captured.remove(o);
+                    Cell::Owned(o)
                 }
+                None => Cell::Empty,
+            };
+            self.replace(mov.to().to_index(), cell);
+        }
+        self.replace(mov.pos.to_index(), Cell::Owned(moved));
+        self.set_next(moved.owner());
+        // boards from FEN or a protocol may not count the moves before them
+        self.plies = self.plies.saturating_sub(1);
+    }
+    /// Play again a move taken back by `undo`, without checking it against the rules.
+    pub fn redo(&mut self, undo: Undo) {
+        let Undo {
+            mov,
+            moved,
+            captured,
+            escaped,
+        } = undo;
+        self.replace(mov.pos.to_index(), Cell::Empty);
+        self.set_next(moved.owner().rev());
+        self.plies = self.plies.saturating_add(1);
+        if escaped {
+            return;
+        }
+        if let Some(o) = captured {
+            self.captured.add(o);
+        }
+        self.replace(mov.to().to_index(), Cell::Owned(moved));
+    }
+    /// Ghosts of `player` and their positions
+    pub fn pieces(&self, player: PlayerID) -> impl Iterator<Item = (Position, OwnedCell)> + '_ {
+        self.inner.iter().enumerate().filter_map(move |(i, c)| match c.to_cell() {
+            Cell::Owned(o) if o.owner() == player => {
+                let pos = Position::new((i % BOARD_WIDTH) as i8, (i / BOARD_WIDTH) as i8);
+                Some((pos, o))
             }
-            None => {
-                if mov.can_escape(from.owner()) && from.ghost() == Ghost::Blue {
-                    Ok(Transition::End(from.owner()))
-                } else {
-                    ErrorKind::InvalidMove(mov).into()
+            _ => None,
+        })
+    }
+    /// Where the ghost `id` of `owner` is, if it's on the board
+    pub fn find(&self, owner: PlayerID, id: GhostID) -> Option<Position> {
+        self.pieces(owner).find(|(_, o)| o.id() == id).map(|(pos, _)| pos)
+    }
+    /// How many ghosts of `owner` with color `ghost` are on the board
+    pub fn count(&self, owner: PlayerID, ghost: Ghost) -> u8 {
+        self.pieces(owner).filter(|(_, o)| o.ghost() == ghost).count() as u8
+    }
+    /// How many ghosts of `owner` are on the board
+    pub fn total(&self, owner: PlayerID) -> u8 {
+        self.pieces(owner).count() as u8
+    }
+    /// Ghosts captured by `transit`
+    pub fn captured(&self) -> &Captured {
+        &self.captured
+    }
+    /// For protocols which report captured ghosts with the board
+    pub fn captured_mut(&mut self) -> &mut Captured {
+        &mut self.captured
+    }
+    /// Classify `mov` by the rules, regardless of which player is to move.
+    pub fn can_move(&self, mov: Move) -> MoveResult {
+        MoveResult::new(self.validate(mov))
+    }
+    /// Rotate the board by 180 degrees and swap the owners of all ghosts.
+    pub fn flipped(&self) -> Self {
+        let mut res = Self::default();
+        for (x, y) in Self::iter() {
+            let pos = Position::new(x, y);
+            let cell = self[pos];
+            let flipped = cell.to_cell().flipped();
+            res.set(pos.flipped(), cell);
+            res.set_cell(pos.flipped(), flipped);
+        }
+        res.set_next(self.next.rev());
+        res.captured = self.captured.flipped();
+        res.rules = self.rules;
+        res.plies = self.plies;
+        res
+    }
+    /// The board seen from `player`, who always plays as P2 from the bottom.
+    /// Moves in this board can be converted back by `Move::perspective`.
+    pub fn perspective(&self, player: PlayerID) -> Self {
+        match player {
+            PlayerID::P1 => self.flipped(),
+            PlayerID::P2 => self.clone(),
+        }
+    }
+    /// What `player` is allowed to see: colors of the opponent's ghosts are hidden.
+    pub fn view_for(&self, player: PlayerID) -> Self {
+        let mut view = self.clone();
+        for (x, y) in Self::iter() {
+            let pos = Position::new(x, y);
+            if let Cell::Owned(o) = self[pos].to_cell() {
+                if o.owner() != player {
+                    view.set_cell(pos, Cell::owned(Ghost::Unknown, o.owner(), o.id()));
                 }
             }
         }
+        view
     }
-    pub fn can_move(&self, mov: Move) -> MoveResult {
-        if !mov.pos.is_valid() {
-            return MoveResult::Err;
+    /// Check that the board reveals no color which `player` shouldn't know.
+    pub fn is_view_for(&self, player: PlayerID) -> bool {
+        self.inner.iter().all(|c| match c.to_cell() {
+            Cell::Owned(o) => o.owner() == player || o.ghost() == Ghost::Unknown,
+            Cell::Empty => true,
+        })
+    }
+    /// Reflect the board horizontally.
+    pub fn mirrored(&self) -> Self {
+        let mut res = self.clone();
+        for (x, y) in Self::iter() {
+            let pos = Position::new(x, y);
+            res.set(pos.mirrored(), self[pos]);
         }
-        let to_p = mov.to();
-        let (from, to) = mov.to_indices();
-        let (owner, ghost) = match self.inner[from].to_cell() {
-            Cell::Owned(o) => (o.owner(), o.ghost()),
-            Cell::Empty => return MoveResult::Err,
-        };
-        if to_p.is_valid() {
-            match self.inner[to].to_cell() {
-                Cell::Empty => true,
-                Cell::Owned(o) => o.owner() != owner,
-            }.into()
+        res
+    }
+    /// The lexicographically smaller one of the board and its mirror, and
+    /// whether it is the mirror. Moves in it can be converted back by `Move::mirrored`.
+    pub fn canonical(&self) -> (Self, bool) {
+        fn key(cell: Cell) -> u16 {
+            match cell {
+                Cell::Owned(o) => u16::from(o.0) + 1,
+                Cell::Empty => 0,
+            }
+        }
+        let mirrored = self.mirrored();
+        let lhs = self.inner.iter().map(|c| key(c.to_cell()));
+        let rhs = mirrored.inner.iter().map(|c| key(c.to_cell()));
+        if rhs.lt(lhs) {
+            (mirrored, true)
         } else {
-            if mov.can_escape(owner) && ghost == Ghost::Blue {
-                MoveResult::Win
-            } else {
-                MoveResult::Err                    
-            }                
+            (self.clone(), false)
+        }
+    }
+    /// All legal moves of `player` with their results
+    pub fn legal_moves(&self, player: PlayerID) -> Vec<(Move, MoveResult)> {
+        let mut buf = Vec::with_capacity(32);
+        self.legal_moves_into(player, &mut buf);
+        buf
+    }
+    /// Same as `legal_moves`, but reuses `buf`.
+    pub fn legal_moves_into(&self, player: PlayerID, buf: &mut Vec<(Move, MoveResult)>) {
+        buf.clear();
+        for (x, y) in Self::iter() {
+            let pos = Position::new(x, y);
+            if self[pos].as_cell().owner() != Some(player) {
+                continue;
+            }
+            for direction in Direction::iter() {
+                let mov = Move { pos, direction };
+                match self.can_move(mov) {
+                    MoveResult::Illegal(_) => continue,
+                    res => buf.push((mov, res)),
+                }
+            }
+        }
+    }
+    /// Find the move of `player` which turns this board into `next`. Only cells are
+    /// compared, so `next` can come from a protocol without captured ghosts.
+    /// Unknown ghosts may escape, as they can be blue.
+    /// Fails unless exactly one legal move explains the difference.
+    pub fn infer_move(&self, next: &Self, player: PlayerID) -> Result<Undo, ErrorKind> {
+        let mut board = self.clone();
+        board.set_next(player);
+        board.set_rules(RuleSet {
+            escape: EscapeRule::AnyColor,
+            ..self.rules
+        });
+        let mut found = None;
+        for (mov, _) in board.legal_moves(player) {
+            let undo = board.apply(mov)?;
+            let ghost = undo.moved.ghost();
+            if undo.escaped && ghost != Ghost::Unknown && !self.rules.can_escape(ghost) {
+                board.undo(undo);
+                continue;
+            }
+            let same = board.diff(next).is_empty();
+            board.undo(undo);
+            if same {
+                if found.is_some() {
+                    return ErrorKind::UnknownMove(player).into();
+                }
+                found = Some(undo);
+            }
         }
+        found.ok_or(ErrorKind::UnknownMove(player))
+    }
+    /// What the move of `player` which turns this board into `next` did
+    pub fn infer_event(&self, next: &Self, player: PlayerID) -> Result<GameEvent, ErrorKind> {
+        self.infer_move(next, player).map(|undo| undo.event())
     }
+    /// Cells which differ from `other`. Use `infer_event` for what the move did.
     pub fn diff(&self, other: &Self) -> Vec<Diff> {
         let mut out = vec![];
         for x in 0..BOARD_WIDTH {
@@ -486,25 +998,10 @@ impl<C: AsCell> Index<Position> for GenericBoard<C> {
     }
 }
 
-impl<C: AsCell> IndexMut<Position> for GenericBoard<C> {
-    fn index_mut(&mut self, pos: Position) -> &mut C {
-        debug_assert!(pos.is_valid());
-        &mut self.inner[pos.to_index()]
-    }
-}
-
+/// Plain ASCII with coordinates. See `render::Renderer` for other styles.
 impl<C: AsCell> fmt::Debug for GenericBoard<C> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "player1")?;
-        writeln!(f, "  0 1 2 3 4 5")?;
-        for y in 0..BOARD_HEIGHT {
-            write!(f, "{}", y)?;
-            for x in 0..BOARD_WIDTH {
-                write!(f, "{} ", self[Position::new(x as i8, y as i8)].as_cell())?;
-            }
-            writeln!(f)?;
-        }
-        writeln!(f, "player2")
+        write!(f, "{}", Renderer::default().display(self))
     }
 }
 
@@ -519,3 +1016,381 @@ fn print_board() {
     let board = Board::init_for_player(pos, PlayerID::P1).unwrap();
     println!("{:?}", board);
 }
+
+#[test]
+fn transit_errors() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let mut board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let mov = |x, y, direction| Move {
+        pos: Position::new(x, y),
+        direction,
+    };
+    let cases = [
+        (mov(0, 0, Direction::Down), ErrorKind::EmptySource(Position::new(0, 0))),
+        (mov(1, 0, Direction::Up), ErrorKind::OffBoard(mov(1, 0, Direction::Up))),
+        (mov(6, 0, Direction::Up), ErrorKind::OffBoard(mov(6, 0, Direction::Up))),
+        (mov(1, 0, Direction::Down), ErrorKind::FriendlyCapture(mov(1, 0, Direction::Down))),
+    ];
+    for &(m, e) in &cases {
+        assert_eq!(board.transit(m), Err(e));
+        assert_eq!(board.can_move(m), MoveResult::Illegal(e));
+    }
+    let opponent = mov(1, 4, Direction::Up);
+    assert_eq!(board.transit(opponent), Err(ErrorKind::NotYourPiece(opponent)));
+    assert_eq!(
+        board.transit_by(PlayerID::P2, mov(1, 4, Direction::Up)),
+        Err(ErrorKind::WrongPlayer(PlayerID::P2))
+    );
+    // a red ghost walks to the corner but can't escape
+    board.transit(mov(1, 0, Direction::Left)).unwrap();
+    board.set_next(PlayerID::P1);
+    for y in 0..5 {
+        board.transit(mov(0, y, Direction::Down)).unwrap();
+        board.set_next(PlayerID::P1);
+    }
+    let escape = mov(0, 5, Direction::Left);
+    assert_eq!(board.transit(escape), Err(ErrorKind::CannotEscape(escape)));
+    assert_eq!(board.can_move(escape), MoveResult::Illegal(ErrorKind::CannotEscape(escape)));
+    let event = board.transit(mov(0, 5, Direction::Up)).unwrap();
+    assert!(matches!(event, GameEvent::Moved { to, .. } if to == Position::new(0, 4)));
+    assert_eq!(board.next(), PlayerID::P2);
+}
+
+#[test]
+fn legal_moves() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let moves = board.legal_moves(PlayerID::P1);
+    // 4 ghosts on y = 1 can go down, 2 of them go sideways, 2 on the edge of y = 0 too
+    assert_eq!(moves.len(), 8);
+    assert!(moves
+        .iter()
+        .all(|&(m, r)| r == MoveResult::Quiet && board[m.pos].owner() == Some(PlayerID::P1)));
+    let mut buf = vec![];
+    board.legal_moves_into(PlayerID::P2, &mut buf);
+    assert_eq!(buf.len(), 8);
+}
+
+#[test]
+fn apply_and_undo() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let mut board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let start = board.clone();
+    let mut undos = vec![];
+    // P1's ghost at (1, 1) goes straight down and captures the ghost at (1, 4)
+    for &(x, y, direction) in &[
+        (1, 1, Direction::Down),
+        (4, 4, Direction::Up),
+        (1, 2, Direction::Down),
+        (4, 3, Direction::Up),
+        (1, 3, Direction::Down),
+    ] {
+        let mov = Move {
+            pos: Position::new(x, y),
+            direction,
+        };
+        undos.push(board.apply(mov).unwrap());
+    }
+    let last = undos.last().unwrap();
+    assert_eq!(last.captured.map(|o| o.owner()), Some(PlayerID::P2));
+    assert_eq!(board.captured().total(PlayerID::P2), 1);
+    while let Some(undo) = undos.pop() {
+        board.undo(undo);
+    }
+    assert!(start.diff(&board).is_empty());
+    assert_eq!(board.next(), PlayerID::P1);
+    assert_eq!(board.captured(), start.captured());
+}
+
+#[test]
+fn turn_limit() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let mut board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let mut undos = vec![];
+    for m in "b2S e5N b3S".split_whitespace() {
+        undos.push(board.apply(m.parse().unwrap()).unwrap());
+    }
+    assert_eq!(board.plies(), 3);
+    assert_eq!(board.remaining_plies(), Some(297));
+    while let Some(undo) = undos.pop() {
+        board.undo(undo);
+    }
+    assert_eq!(board.plies(), 0);
+    board.set_plies(300);
+    assert!(board.is_turn_limit());
+    board.set_rules(RuleSet::TEACHING);
+    assert_eq!(board.remaining_plies(), None);
+    assert!(!board.is_turn_limit());
+    // no overflow without a limit
+    board.set_plies(u32::MAX);
+    let undo = board.apply("b2S".parse().unwrap()).unwrap();
+    assert_eq!(board.plies(), u32::MAX);
+    board.undo(undo);
+    board.set_plies(0);
+    let undo = board.apply("b2S".parse().unwrap()).unwrap();
+    board.set_plies(0);
+    board.undo(undo);
+    assert_eq!(board.plies(), 0);
+}
+
+#[test]
+fn zobrist_transposition() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let start = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let mov = |x, y, direction| Move {
+        pos: Position::new(x, y),
+        direction,
+    };
+    let play = |moves: &[Move]| {
+        let mut board = start.clone();
+        for &m in moves {
+            board.transit(m).unwrap();
+        }
+        board
+    };
+    let (a, b, c, d) = (
+        mov(1, 1, Direction::Down),
+        mov(1, 4, Direction::Up),
+        mov(4, 1, Direction::Down),
+        mov(4, 4, Direction::Up),
+    );
+    let board1 = play(&[a, b, c, d]);
+    let board2 = play(&[c, d, a, b]);
+    assert_eq!(board1, board2);
+    assert_eq!(board1.zobrist(), board2.zobrist());
+    assert_ne!(board1, play(&[a, b, c]));
+    assert_ne!(board1.zobrist(), start.zobrist());
+    // the hash only depends on the position
+    let mut rebuilt = Board::default();
+    for (x, y) in Board::iter() {
+        let pos = Position::new(x, y);
+        rebuilt.set(pos, board1[pos]);
+    }
+    assert_eq!(rebuilt.zobrist(), board1.zobrist());
+    let mut undone = board1.clone();
+    let undo = undone.apply(mov(1, 2, Direction::Down)).unwrap();
+    assert_ne!(undone.zobrist(), board1.zobrist());
+    undone.undo(undo);
+    assert_eq!(undone, board1);
+    assert_eq!(undone.zobrist(), board1.zobrist());
+}
+
+#[test]
+fn flip_board() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 1),
+        Position::new(3, 0),
+        Position::new(4, 1),
+    ];
+    let board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let flipped_red = [
+        red[0].flipped(),
+        red[1].flipped(),
+        red[2].flipped(),
+        red[3].flipped(),
+    ];
+    let mut expected = Board::init_for_player(flipped_red, PlayerID::P2).unwrap();
+    expected.set_next(PlayerID::P2);
+    assert_eq!(board.flipped(), expected);
+    assert_eq!(board.flipped().flipped(), board);
+    assert_eq!(board.perspective(PlayerID::P2), board);
+    // a move in the perspective of P1 is the same move in absolute coordinates
+    let mov = Move {
+        pos: Position::new(1, 1),
+        direction: Direction::Down,
+    };
+    let mut view = board.perspective(PlayerID::P1);
+    let mut abs = board.clone();
+    view.transit(mov.perspective(PlayerID::P1)).unwrap();
+    abs.transit(mov).unwrap();
+    assert_eq!(view.perspective(PlayerID::P1), abs);
+}
+
+#[test]
+fn mirror_board() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 1),
+        Position::new(3, 0),
+        Position::new(3, 1),
+    ];
+    let board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let mirrored = board.mirrored();
+    assert_ne!(mirrored, board);
+    assert_eq!(mirrored.mirrored(), board);
+    assert_eq!(
+        mirrored[Position::new(4, 0)],
+        Cell::owned(Ghost::Red, PlayerID::P1, GhostID::H)
+    );
+    assert_eq!(board.canonical().0, mirrored.canonical().0);
+    assert_ne!(board.canonical().1, mirrored.canonical().1);
+    let mov = Move {
+        pos: Position::new(1, 1),
+        direction: Direction::Left,
+    };
+    let (mut a, mut b) = (board.clone(), mirrored);
+    a.transit(mov).unwrap();
+    b.transit(mov.mirrored()).unwrap();
+    assert_eq!(a.mirrored(), b);
+}
+
+#[test]
+fn view_for_player() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let p2 = board.flipped();
+    assert!(board.is_view_for(PlayerID::P1));
+    assert!(!p2.is_view_for(PlayerID::P1));
+    let view = p2.view_for(PlayerID::P1);
+    assert!(view.is_view_for(PlayerID::P1));
+    assert!(view.view_for(PlayerID::P2).is_view_for(PlayerID::P2));
+    assert_eq!(view.legal_moves(PlayerID::P1), p2.legal_moves(PlayerID::P1));
+}
+
+#[test]
+fn notation() {
+    let pos = Position::new(1, 1);
+    assert_eq!(pos.to_string(), "b2");
+    assert_eq!("f6".parse(), Ok(Position::new(5, 5)));
+    let mov = Move {
+        pos,
+        direction: Direction::Up,
+    };
+    assert_eq!(mov.to_string(), "b2N");
+    assert_eq!("b2N".parse(), Ok(mov));
+    assert_eq!(ErrorKind::OffBoard(mov).to_string(), "Moving out of the board b2N");
+    assert_eq!("C".parse(), Ok(GhostID::C));
+    for s in &["g1", "a0", "a10", "b"] {
+        assert_eq!(s.parse::<Position>(), Err(ParseError::Position(s.to_string())));
+    }
+    assert!("b2X".parse::<Move>().is_err());
+    assert!("b2NN".parse::<Move>().is_err());
+    assert!("I".parse::<GhostID>().is_err());
+    assert!("a".parse::<GhostID>().is_err());
+}
+
+#[test]
+fn pieces_and_counts() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let mut board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    assert_eq!(board.pieces(PlayerID::P1).count(), 8);
+    assert!(board
+        .pieces(PlayerID::P2)
+        .all(|(pos, o)| board[pos] == Cell::Owned(o) && o.owner() == PlayerID::P2));
+    assert_eq!(board.find(PlayerID::P1, GhostID::H), Some(Position::new(1, 0)));
+    assert_eq!(board.find(PlayerID::P2, GhostID::A), Some(Position::new(1, 4)));
+    assert_eq!(board.count(PlayerID::P1, Ghost::Red), 4);
+    assert_eq!(board.count(PlayerID::P2, Ghost::Unknown), 8);
+    // P1's blue ghost G captures P2's ghost A
+    for m in "b2S e5N b3S e4N".split_whitespace() {
+        board.transit(m.parse().unwrap()).unwrap();
+    }
+    let capture = "b4S".parse().unwrap();
+    match board.can_move(capture) {
+        MoveResult::Capture(o) => assert_eq!(o.id(), GhostID::A),
+        res => panic!("{:?}", res),
+    }
+    board.transit(capture).unwrap();
+    assert_eq!(board.find(PlayerID::P2, GhostID::A), None);
+    assert_eq!(board.find(PlayerID::P1, GhostID::G), Some(Position::new(1, 4)));
+    assert_eq!(board.total(PlayerID::P2), 7);
+    assert_eq!(board.captured().left_total(PlayerID::P2), 7);
+    assert_eq!(board.captured().count(PlayerID::P2, Ghost::Unknown), 1);
+    assert_eq!(board.captured().left(PlayerID::P2, Ghost::Unknown), 0);
+    assert_eq!(board.captured().left(PlayerID::P2, Ghost::Red), 4);
+}
+
+#[test]
+fn infer_move() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let mut board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let mut prev = board.clone();
+    // the last move captures P2's ghost A
+    for m in "b2S e5N b3S e4N b4S".split_whitespace() {
+        let mov: Move = m.parse().unwrap();
+        prev = board.clone();
+        board.transit(mov).unwrap();
+        let undo = prev.infer_move(&board, prev.next()).unwrap();
+        assert_eq!(undo.mov, mov);
+        assert_eq!(Cell::Owned(undo.moved), prev[mov.pos]);
+    }
+    let captured = prev.infer_move(&board, PlayerID::P1).unwrap().captured;
+    assert_eq!(captured.map(|o| o.id()), Some(GhostID::A));
+    let err = |p| Err(ErrorKind::UnknownMove(p));
+    assert_eq!(prev.infer_move(&board, PlayerID::P2), err(PlayerID::P2));
+    assert_eq!(prev.infer_move(&prev, PlayerID::P1), err(PlayerID::P1));
+}
+
+#[test]
+fn infer_event() {
+    let event = |fen: &str, mov: &str| {
+        let before = Board::from_fen(fen).unwrap();
+        let mut after = before.clone();
+        let event = after.transit(mov.parse().unwrap()).unwrap();
+        assert_eq!(before.infer_event(&after, before.next()), Ok(event));
+        event
+    };
+    // P1's blue ghost A on the escape square a6 and P2's ghost A next to it
+    let fen = "6/6/6/6/6/Aba?4 1 000/000 b300 0";
+    assert!(matches!(event(fen, "a6W"), GameEvent::Escaped { .. }));
+    assert!(matches!(event(fen, "a6N"), GameEvent::Moved { .. }));
+    let fen = "6/6/6/6/6/Aba?4 2 000/000 b300 0";
+    match event(fen, "b6W") {
+        GameEvent::Captured { victim, .. } => assert_eq!(victim.ghost(), Ghost::Blue),
+        e => panic!("{:?}", e),
+    }
+    let fen = "6/6/6/6/6/Ar5 1 000/000 a- 0";
+    assert!(matches!(event(fen, "a6W"), GameEvent::Escaped { .. }));
+    // P1's view can't tell whether P2's ghost A is blue, so it may escape
+    let after = Board::from_fen("6/6/6/6/6/Ab5 1 000/000 b300 1").unwrap();
+    let view = Board::from_fen("a?5/6/6/6/6/Ab5 2 000/000 b300 0").unwrap();
+    let undo = view.infer_move(&after, PlayerID::P2).unwrap();
+    assert_eq!(undo.mov, "a1W".parse().unwrap());
+    assert!(undo.escaped);
+    let mut redone = view.clone();
+    redone.redo(undo);
+    assert_eq!(redone, after);
+    let full = Board::from_fen("ar5/6/6/6/6/Ab5 2 000/000 b300 0").unwrap();
+    assert_eq!(full.infer_move(&after, PlayerID::P2), Err(ErrorKind::UnknownMove(PlayerID::P2)));
+}
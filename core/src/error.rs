@@ -1,13 +1,56 @@
-use crate::board::Move;
+use crate::board::{Move, Position};
+use crate::player::PlayerID;
 
 #[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ErrorKind {
-    #[display(fmt = "Invalid Move {:?}", _0)]
-    InvalidMove(Move),
+    #[display(fmt = "No ghost at {}", _0)]
+    EmptySource(Position),
+    #[display(fmt = "Moving a ghost of the opponent {}", _0)]
+    NotYourPiece(Move),
+    #[display(fmt = "Moving out of the board {}", _0)]
+    OffBoard(Move),
+    #[display(fmt = "Capturing an own ghost {}", _0)]
+    FriendlyCapture(Move),
+    #[display(fmt = "Only a blue ghost can escape {}", _0)]
+    CannotEscape(Move),
+    #[display(fmt = "{:?} is not the player to move", _0)]
+    WrongPlayer(PlayerID),
+    #[display(fmt = "{:?} can't place a ghost at {}", _0, _1)]
+    OutOfInitArea(PlayerID, Position),
+    #[display(fmt = "{:?} placed two red ghosts at {}", _0, _1)]
+    DuplicatePlacement(PlayerID, Position),
+    #[display(fmt = "{:?} placed {} red ghosts", _0, _1)]
+    RedCount(PlayerID, u8),
+    #[display(fmt = "The boards are not connected by a move of {:?}", _0)]
+    UnknownMove(PlayerID),
+    #[display(fmt = "The game is over")]
+    GameOver,
 }
 
-impl<T> Into<Result<T, ErrorKind>> for ErrorKind {
-    fn into(self) -> Result<T, ErrorKind> {
-        Err(self)
+impl<T> From<ErrorKind> for Result<T, ErrorKind> {
+    fn from(e: ErrorKind) -> Self {
+        Err(e)
     }
 }
+
+/// Error of parsing the notation of moves and positions
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum ParseError {
+    #[display(fmt = "Invalid position {:?}", _0)]
+    Position(String),
+    #[display(fmt = "Invalid direction {:?}", _0)]
+    Direction(String),
+    #[display(fmt = "Invalid move {:?}", _0)]
+    Move(String),
+    #[display(fmt = "Invalid ghost ID {:?}", _0)]
+    GhostID(String),
+    #[display(fmt = "Invalid rules {:?}", _0)]
+    Rules(String),
+    #[display(fmt = "Invalid FEN {:?}", _0)]
+    Fen(String),
+    #[display(fmt = "Invalid record at line {}: {}", _0, _1)]
+    Record(usize, String),
+    #[display(fmt = "Missing tag {} in the record", _0)]
+    MissingTag(String),
+}
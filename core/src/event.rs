@@ -0,0 +1,23 @@
+use crate::board::{OwnedCell, Position};
+use crate::outcome::Outcome;
+
+/// Something that happened in a game
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameEvent {
+    /// `ghost` moved from `from` to the empty square `to`
+    Moved {
+        from: Position,
+        to: Position,
+        ghost: OwnedCell,
+    },
+    /// `by` moved to `pos` and captured `victim`, whose color is revealed
+    Captured {
+        by: OwnedCell,
+        victim: OwnedCell,
+        pos: Position,
+    },
+    /// `ghost` left the board from `pos`
+    Escaped { ghost: OwnedCell, pos: Position },
+    GameOver(Outcome),
+}
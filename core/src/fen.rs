@@ -0,0 +1,259 @@
+//! FEN-like encoding of a single position
+//!
+//! `1HrFrDrBr1/1GbEbCbAb1/6/6/1a?c?e?g?1/1b?d?f?h?1 1 000/000 b300 0`
+//!
+//! - Rows from `y = 0`, separated by `/`. Each ghost is its ID, uppercase for P1
+//!   and lowercase for P2, followed by `r`(red), `b`(blue) or `?`(unknown).
+//!   Digits are runs of empty cells.
+//! - The player to move, `1` or `2`.
+//! - Numbers of captured red, blue and unknown ghosts of P1 and P2.
+//! - The rules, written as `RuleSet` is displayed.
+//! - The number of plies played.
+use crate::board::*;
+use crate::error::ParseError;
+use crate::outcome::{NUM_BLUE, NUM_RED};
+use crate::player::PlayerID;
+
+const COLORS: [Ghost; 3] = [Ghost::Red, Ghost::Blue, Ghost::Unknown];
+
+fn color_char(ghost: Ghost) -> char {
+    match ghost {
+        Ghost::Red => 'r',
+        Ghost::Blue => 'b',
+        Ghost::Unknown => '?',
+    }
+}
+
+impl<C: AsCell> GenericBoard<C> {
+    pub fn to_fen(&self) -> String {
+        let mut res = String::new();
+        for y in 0..BOARD_HEIGHT {
+            if y > 0 {
+                res.push('/');
+            }
+            let mut empty = 0;
+            for x in 0..BOARD_WIDTH {
+                match self[Position::new(x as i8, y as i8)].to_cell() {
+                    Cell::Owned(o) => {
+                        if empty > 0 {
+                            res.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        let id = (b'A' + o.id().as_u8()) as char;
+                        res.push(match o.owner() {
+                            PlayerID::P1 => id,
+                            PlayerID::P2 => id.to_ascii_lowercase(),
+                        });
+                        res.push(color_char(o.ghost()));
+                    }
+                    Cell::Empty => empty += 1,
+                }
+            }
+            if empty > 0 {
+                res.push_str(&empty.to_string());
+            }
+        }
+        res.push_str(match self.next() {
+            PlayerID::P1 => " 1 ",
+            PlayerID::P2 => " 2 ",
+        });
+        for (i, &owner) in [PlayerID::P1, PlayerID::P2].iter().enumerate() {
+            if i > 0 {
+                res.push('/');
+            }
+            for &ghost in &COLORS {
+                res.push_str(&self.captured().count(owner, ghost).to_string());
+            }
+        }
+        res.push(' ');
+        res.push_str(&self.rules().to_string());
+        res.push(' ');
+        res.push_str(&self.plies().to_string());
+        res
+    }
+}
+
+impl Board {
+    pub fn from_fen(s: &str) -> Result<Self, ParseError> {
+        let err = || ParseError::Fen(s.to_owned());
+        let mut fields = s.split_whitespace();
+        let mut field = || fields.next().ok_or_else(err);
+        let (cells, next, captured, rules, plies) =
+            (field()?, field()?, field()?, field()?, field()?);
+        if fields.next().is_some() {
+            return Err(err());
+        }
+        let mut board = Board::default();
+        let mut seen = [[false; 8]; 2];
+        let rows: Vec<_> = cells.split('/').collect();
+        if rows.len() != BOARD_HEIGHT {
+            return Err(err());
+        }
+        for (y, row) in rows.iter().enumerate() {
+            let mut x = 0;
+            // a run of empty cells is one nonzero digit, so each board has one FEN
+            let mut after_digit = false;
+            let mut chars = row.chars();
+            while let Some(c) = chars.next() {
+                if let Some(d) = c.to_digit(10) {
+                    if d == 0 || after_digit {
+                        return Err(err());
+                    }
+                    after_digit = true;
+                    x += d as usize;
+                    continue;
+                }
+                after_digit = false;
+                let (owner, id) = match c {
+                    'A'..='H' => (PlayerID::P1, c as u8 - b'A'),
+                    'a'..='h' => (PlayerID::P2, c as u8 - b'a'),
+                    _ => return Err(err()),
+                };
+                let ghost = match chars.next() {
+                    Some('r') => Ghost::Red,
+                    Some('b') => Ghost::Blue,
+                    Some('?') => Ghost::Unknown,
+                    _ => return Err(err()),
+                };
+                let seen = &mut seen[owner as usize][usize::from(id)];
+                if x >= BOARD_WIDTH || *seen {
+                    return Err(err());
+                }
+                *seen = true;
+                let id = GhostID::from_u8(id).unwrap();
+                board.set(Position::new(x as i8, y as i8), Cell::owned(ghost, owner, id));
+                x += 1;
+            }
+            if x != BOARD_WIDTH {
+                return Err(err());
+            }
+        }
+        board.set_next(match next {
+            "1" => PlayerID::P1,
+            "2" => PlayerID::P2,
+            _ => return Err(err()),
+        });
+        let counts: Vec<_> = captured.split('/').collect();
+        if counts.len() != 2 {
+            return Err(err());
+        }
+        for (&owner, counts) in [PlayerID::P1, PlayerID::P2].iter().zip(counts) {
+            let digits: Vec<_> = counts.chars().map(|c| c.to_digit(10)).collect();
+            if digits.len() != COLORS.len() {
+                return Err(err());
+            }
+            for (&ghost, d) in COLORS.iter().zip(digits) {
+                let d = d.ok_or_else(err)?;
+                board.captured_mut().set_count(owner, ghost, d as u8);
+            }
+        }
+        // captured and remaining ghosts can't be more than the ghosts of a player
+        for &owner in &[PlayerID::P1, PlayerID::P2] {
+            let captured = board.captured();
+            let red = captured.count(owner, Ghost::Red) + board.count(owner, Ghost::Red);
+            let blue = captured.count(owner, Ghost::Blue) + board.count(owner, Ghost::Blue);
+            let total = captured.total(owner) + board.total(owner);
+            if red > NUM_RED || blue > NUM_BLUE || total > NUM_RED + NUM_BLUE {
+                return Err(err());
+            }
+        }
+        board.set_rules(rules.parse().map_err(|_| err())?);
+        board.set_plies(plies.parse().map_err(|_| err())?);
+        Ok(board)
+    }
+}
+
+/// Boards are serialized as FEN strings.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Board {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_fen())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Board {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        Board::from_fen(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[test]
+fn fen_roundtrip() {
+    use crate::rules::RuleSet;
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let mut board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let fen = board.to_fen();
+    assert_eq!(fen, "1HrFrDrBr1/1GbEbCbAb1/6/6/1a?c?e?g?1/1b?d?f?h?1 1 000/000 b300 0");
+    assert_eq!(Board::from_fen(&fen), Ok(board.clone()));
+    for m in "b2S b5N b3S c5N".split_whitespace() {
+        board.transit(m.parse().unwrap()).unwrap();
+    }
+    let fen = board.to_fen();
+    assert_eq!(fen, "1HrFrDrBr1/2EbCbAb1/6/1Gbc?3/3e?g?1/1b?d?f?h?1 1 000/001 b300 4");
+    assert_eq!(Board::from_fen(&fen), Ok(board.clone()));
+    // plies aren't compared by Eq
+    assert_eq!(Board::from_fen(&fen).unwrap().remaining_plies(), Some(296));
+    board.set_rules(RuleSet::TEACHING);
+    let fen = board.to_fen();
+    assert!(fen.ends_with(" 000/001 a- 4"));
+    assert_eq!(Board::from_fen(&fen), Ok(board));
+    for fen in &[
+        "6/6/6/6/6 1 000/000 b300 0",
+        "7/6/6/6/6/6 1 000/000 b300 0",
+        "Ar5/Ab5/6/6/6/6 1 000/000 b300 0",
+        "Ax5/6/6/6/6/6 1 000/000 b300 0",
+        "6/6/6/6/6/6 3 000/000 b300 0",
+        "6/6/6/6/6/6 1 00/000 b300 0",
+        "6/6/6/6/6/6 1 000/000",
+        "6/6/6/6/6/6 1 000/000 r300 0",
+        "6/6/6/6/6/6 1 000/000 b300",
+        "6/6/6/6/6/6 1 000/000 b300 -1",
+        "6/6/6/6/6/6 1 999/999 b300 0",
+        "6/6/6/6/6/6 1 500/000 b300 0",
+        "Ar5/6/6/6/6/6 1 400/000 b300 0",
+        "6/6/6/6/6/6 1 036/000 b300 0",
+        "1HrFrDrBr1/1GbEbCbAb1/6/6/6/6 1 001/000 b300 0",
+        "06/6/6/6/6/6 1 000/000 b300 0",
+        "33/6/6/6/6/6 1 000/000 b300 0",
+        "1HrFrDrBr01/6/6/6/6/6 1 000/000 b300 0",
+    ] {
+        assert!(Board::from_fen(fen).is_err(), "{}", fen);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_json() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let json = serde_json::to_string(&board).unwrap();
+    assert_eq!(json, format!("\"{}\"", board.to_fen()));
+    assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+    let cell = board[Position::new(1, 0)];
+    let json = serde_json::to_string(&cell).unwrap();
+    assert_eq!(json, r#"{"Owned":{"owner":"P1","ghost":"Red","id":"H"}}"#);
+    assert_eq!(serde_json::from_str::<Cell>(&json).unwrap(), cell);
+    let mov: Move = "b2S".parse().unwrap();
+    let json = serde_json::to_string(&mov).unwrap();
+    assert_eq!(json, r#"{"pos":{"x":1,"y":1},"direction":"Down"}"#);
+    let board = Board::from_fen("6/6/6/6/6/6 1 000/102 b300 0").unwrap();
+    let captured = *board.captured();
+    let json = serde_json::to_string(&captured).unwrap();
+    assert_eq!(
+        json,
+        r#"{"P1":{"red":0,"blue":0,"unknown":0},"P2":{"red":1,"blue":0,"unknown":2}}"#
+    );
+    assert_eq!(serde_json::from_str::<crate::outcome::Captured>(&json).unwrap(), captured);
+}
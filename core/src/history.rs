@@ -0,0 +1,126 @@
+use crate::board::{Board, Move, Undo};
+use crate::error::ErrorKind;
+use crate::event::GameEvent;
+
+/// A game from its initial board, with every move and its events.
+/// The current board can jump to any ply and back without losing moves.
+#[derive(Clone, Debug)]
+pub struct GameHistory {
+    initial: Board,
+    board: Board,
+    undos: Vec<Undo>,
+    /// Zobrist hashes of the initial board and the boards after each move
+    hashes: Vec<u64>,
+    /// The number of moves applied to `board`
+    ply: usize,
+}
+
+impl GameHistory {
+    pub fn new(initial: Board) -> Self {
+        GameHistory {
+            hashes: vec![initial.zobrist()],
+            board: initial.clone(),
+            initial,
+            undos: vec![],
+            ply: 0,
+        }
+    }
+    pub fn initial(&self) -> &Board {
+        &self.initial
+    }
+    /// The board at the current ply
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+    /// The number of recorded moves
+    pub fn len(&self) -> usize {
+        self.undos.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.undos.is_empty()
+    }
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.undos.iter().map(|u| u.mov)
+    }
+    pub fn events(&self) -> impl Iterator<Item = GameEvent> + '_ {
+        self.undos.iter().map(|u| u.event())
+    }
+    /// Play `mov` on the current board. Moves after the current ply are discarded.
+    pub fn push(&mut self, mov: Move) -> Result<GameEvent, ErrorKind> {
+        let undo = self.board.apply(mov)?;
+        self.undos.truncate(self.ply);
+        self.hashes.truncate(self.ply + 1);
+        self.undos.push(undo);
+        self.hashes.push(self.board.zobrist());
+        self.ply += 1;
+        Ok(undo.event())
+    }
+    /// Remove the last recorded move and go to the ply before it.
+    pub fn undo_last(&mut self) -> Option<Undo> {
+        let last = self.undos.len().checked_sub(1)?;
+        self.jump(last);
+        self.hashes.pop();
+        self.undos.pop()
+    }
+    /// Go to the board after `ply` moves. Returns `false` if there aren't so many moves.
+    pub fn jump(&mut self, ply: usize) -> bool {
+        if ply > self.undos.len() {
+            return false;
+        }
+        while self.ply > ply {
+            self.ply -= 1;
+            self.board.undo(self.undos[self.ply]);
+        }
+        while self.ply < ply {
+            let undo = self.board.apply(self.undos[self.ply].mov);
+            debug_assert_eq!(undo, Ok(self.undos[self.ply]));
+            self.ply += 1;
+        }
+        true
+    }
+    /// How many times the current position has appeared, including now
+    pub fn repetitions(&self) -> usize {
+        let hash = self.board.zobrist();
+        self.hashes[..=self.ply].iter().filter(|&&h| h == hash).count()
+    }
+}
+
+#[test]
+fn history_repetition() {
+    use crate::player::PlayerID;
+    use crate::setup::SetupBuilder;
+    let pos = |s: &str| -> Vec<_> { s.split_whitespace().map(|p| p.parse().unwrap()).collect() };
+    let initial = SetupBuilder::new()
+        .red(PlayerID::P1, &pos("b1 c1 d1 e1"))
+        .red(PlayerID::P2, &pos("b6 c6 d6 e6"))
+        .build()
+        .unwrap();
+    let mut history = GameHistory::new(initial.clone());
+    // both players shuffle back and forth
+    for _ in 0..2 {
+        for m in "b2S e5N b3N e4S".split_whitespace() {
+            history.push(m.parse().unwrap()).unwrap();
+        }
+    }
+    assert_eq!(history.len(), 8);
+    assert_eq!(history.repetitions(), 3);
+    assert!(history.jump(4));
+    assert_eq!(history.repetitions(), 2);
+    assert!(history.jump(0));
+    assert_eq!(history.board(), &initial);
+    assert!(!history.jump(9));
+    assert!(history.jump(8));
+    let undo = history.undo_last().unwrap();
+    assert_eq!(undo.mov, "e4S".parse().unwrap());
+    assert_eq!((history.len(), history.ply()), (7, 7));
+    assert_eq!(history.repetitions(), 2);
+    // a new move from the middle discards the rest
+    history.jump(2);
+    history.push("b3E".parse().unwrap()).unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.moves().last(), Some("b3E".parse().unwrap()));
+    assert!(history.push("b1N".parse().unwrap()).is_err());
+}
@@ -1,6 +1,17 @@
 #[macro_use]
 extern crate derive_more;
+pub mod bitboard;
 pub mod board;
 pub mod error;
+pub mod event;
+mod fen;
+pub mod history;
+pub mod observer;
+pub mod outcome;
 pub mod player;
+pub mod record;
+pub mod render;
+pub mod rules;
+pub mod setup;
 pub mod simulator;
+mod zobrist;
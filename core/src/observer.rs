@@ -0,0 +1,29 @@
+use crate::board::{Board, Move};
+use crate::event::GameEvent;
+use crate::outcome::Outcome;
+use crate::player::PlayerID;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Watches a game without playing it, e.g. loggers, renderers and statistics.
+/// Boards are full-information in `Simulator`, and views of the client over a network.
+pub trait GameObserver {
+    /// The game starts from `board`, which has the setups of both players.
+    fn on_start(&mut self, _board: &Board) {}
+    /// `player` played `mov`, which caused `events`. `board` is the board after it.
+    fn on_move(&mut self, _player: PlayerID, _mov: Move, _events: &[GameEvent], _board: &Board) {}
+    fn on_end(&mut self, _outcome: Outcome) {}
+}
+
+/// Keep a handle to read the results of an observer after the game.
+impl<O: GameObserver> GameObserver for Rc<RefCell<O>> {
+    fn on_start(&mut self, board: &Board) {
+        self.borrow_mut().on_start(board)
+    }
+    fn on_move(&mut self, player: PlayerID, mov: Move, events: &[GameEvent], board: &Board) {
+        self.borrow_mut().on_move(player, mov, events, board)
+    }
+    fn on_end(&mut self, outcome: Outcome) {
+        self.borrow_mut().on_end(outcome)
+    }
+}
@@ -0,0 +1,208 @@
+use crate::board::{Ghost, OwnedCell};
+use crate::player::PlayerID;
+
+pub const NUM_RED: u8 = 4;
+pub const NUM_BLUE: u8 = 4;
+
+/// Why a game finished
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Reason {
+    /// A blue ghost of the winner escaped
+    Escape,
+    /// The winner captured all blue ghosts of the loser
+    AllBlueCaptured,
+    /// All red ghosts of the winner were captured
+    AllRedTaken,
+    /// The game reached the turn limit
+    TurnLimit,
+    /// The loser returned an invalid setup or an illegal move
+    IllegalMove,
+    /// The loser had no legal move to play
+    NoLegalMove,
+    /// The loser didn't return a move in time
+    Timeout,
+    /// The protocol didn't tell why the game finished
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// `None` means a draw
+    pub winner: Option<PlayerID>,
+    pub reason: Reason,
+}
+
+impl Outcome {
+    pub fn win(winner: PlayerID, reason: Reason) -> Self {
+        Outcome {
+            winner: Some(winner),
+            reason,
+        }
+    }
+    pub fn lose(loser: PlayerID, reason: Reason) -> Self {
+        Self::win(loser.rev(), reason)
+    }
+    pub fn draw(reason: Reason) -> Self {
+        Outcome {
+            winner: None,
+            reason,
+        }
+    }
+    pub fn is_draw(&self) -> bool {
+        self.winner.is_none()
+    }
+}
+
+/// Numbers of captured ghosts per owner and color
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "CapturedRepr", into = "CapturedRepr")
+)]
+pub struct Captured([[u8; 3]; 2]);
+
+/// Readable form of `Captured` for serialization
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+struct CapturedRepr {
+    #[serde(rename = "P1")]
+    p1: CountsRepr,
+    #[serde(rename = "P2")]
+    p2: CountsRepr,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+struct CountsRepr {
+    red: u8,
+    blue: u8,
+    unknown: u8,
+}
+
+#[cfg(feature = "serde")]
+impl From<CapturedRepr> for Captured {
+    fn from(r: CapturedRepr) -> Self {
+        let mut captured = Captured::default();
+        for &(owner, counts) in &[(PlayerID::P1, r.p1), (PlayerID::P2, r.p2)] {
+            captured.set_count(owner, Ghost::Red, counts.red);
+            captured.set_count(owner, Ghost::Blue, counts.blue);
+            captured.set_count(owner, Ghost::Unknown, counts.unknown);
+        }
+        captured
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Captured> for CapturedRepr {
+    fn from(c: Captured) -> Self {
+        let counts = |owner| CountsRepr {
+            red: c.count(owner, Ghost::Red),
+            blue: c.count(owner, Ghost::Blue),
+            unknown: c.count(owner, Ghost::Unknown),
+        };
+        CapturedRepr {
+            p1: counts(PlayerID::P1),
+            p2: counts(PlayerID::P2),
+        }
+    }
+}
+
+impl Captured {
+    fn indices(owner: PlayerID, ghost: Ghost) -> (usize, usize) {
+        let owner = match owner {
+            PlayerID::P1 => 0,
+            PlayerID::P2 => 1,
+        };
+        let ghost = match ghost {
+            Ghost::Unknown => 0,
+            Ghost::Red => 1,
+            Ghost::Blue => 2,
+        };
+        (owner, ghost)
+    }
+    /// Record that `cell` was captured.
+    pub fn add(&mut self, cell: OwnedCell) {
+        let (o, g) = Self::indices(cell.owner(), cell.ghost());
+        self.0[o][g] += 1;
+    }
+    /// Cancel a record made by `add`.
+    pub fn remove(&mut self, cell: OwnedCell) {
+        let (o, g) = Self::indices(cell.owner(), cell.ghost());
+        debug_assert!(self.0[o][g] > 0);
+        self.0[o][g] -= 1;
+    }
+    /// How many ghosts of `owner` with color `ghost` were captured
+    pub fn count(&self, owner: PlayerID, ghost: Ghost) -> u8 {
+        let (o, g) = Self::indices(owner, ghost);
+        self.0[o][g]
+    }
+    pub fn set_count(&mut self, owner: PlayerID, ghost: Ghost, count: u8) {
+        let (o, g) = Self::indices(owner, ghost);
+        self.0[o][g] = count;
+    }
+    /// How many ghosts of `owner` were captured
+    pub fn total(&self, owner: PlayerID) -> u8 {
+        let (o, _) = Self::indices(owner, Ghost::Unknown);
+        self.0[o].iter().sum()
+    }
+    /// How many ghosts of `owner` with color `ghost` haven't been captured.
+    /// Every ghost is red or blue, so `Ghost::Unknown` gives 0. See `left_total`.
+    pub fn left(&self, owner: PlayerID, ghost: Ghost) -> u8 {
+        let num = match ghost {
+            Ghost::Red => NUM_RED,
+            Ghost::Blue => NUM_BLUE,
+            Ghost::Unknown => 0,
+        };
+        num.saturating_sub(self.count(owner, ghost))
+    }
+    /// How many ghosts of `owner` haven't been captured
+    pub fn left_total(&self, owner: PlayerID) -> u8 {
+        (NUM_RED + NUM_BLUE).saturating_sub(self.total(owner))
+    }
+    /// Swap the owners
+    pub fn flipped(&self) -> Self {
+        Captured([self.0[1], self.0[0]])
+    }
+    /// Check if the game is over by captures.
+    pub fn outcome(&self) -> Option<Outcome> {
+        for &owner in &[PlayerID::P1, PlayerID::P2] {
+            if self.count(owner, Ghost::Blue) >= NUM_BLUE {
+                return Some(Outcome::lose(owner, Reason::AllBlueCaptured));
+            }
+            if self.count(owner, Ghost::Red) >= NUM_RED {
+                return Some(Outcome::win(owner, Reason::AllRedTaken));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn capture_outcome() {
+    use crate::board::{Cell, GhostID};
+    let owned = |ghost, owner| match Cell::owned(ghost, owner, GhostID::A) {
+        Cell::Owned(o) => o,
+        Cell::Empty => unreachable!(),
+    };
+    let mut captured = Captured::default();
+    for _ in 0..3 {
+        captured.add(owned(Ghost::Blue, PlayerID::P1));
+        captured.add(owned(Ghost::Red, PlayerID::P2));
+    }
+    assert_eq!(captured.outcome(), None);
+    assert_eq!(captured.total(PlayerID::P1), 3);
+    captured.add(owned(Ghost::Red, PlayerID::P2));
+    assert_eq!(
+        captured.outcome(),
+        Some(Outcome::win(PlayerID::P2, Reason::AllRedTaken))
+    );
+    captured.remove(owned(Ghost::Red, PlayerID::P2));
+    captured.add(owned(Ghost::Blue, PlayerID::P1));
+    assert_eq!(
+        captured.outcome(),
+        Some(Outcome::win(PlayerID::P2, Reason::AllBlueCaptured))
+    );
+}
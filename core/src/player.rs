@@ -1,7 +1,8 @@
 use crate::board::{Board, GhostID, Move, Position, BOARD_HEIGHT};
 use std::ops::Range;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PlayerID {
     P1,
     P2,
@@ -0,0 +1,295 @@
+//! Game records (kifu)
+//!
+//! A record is written as tag lines followed by the moves:
+//! ```text
+//! [P1 "random"]
+//! [P2 "yowagoshi"]
+//! [Date "2018-11-03"]
+//! [Red1 "b1 c1 d2 e2"]
+//! [Red2 "b5 c5 d6 e6"]
+//! [Rules "a-"]
+//! [Result "P1 Escape"]
+//! b2W c5N a2S ...
+//! ```
+//! `Red1` and `Red2` are required and the other tags are optional.
+//! `Rules` is written only for variants and defaults to `RuleSet::STANDARD`.
+use crate::board::{Board, Move, Position};
+use crate::error::{ErrorKind, ParseError};
+use crate::outcome::{Outcome, Reason};
+use crate::player::PlayerID;
+use crate::rules::RuleSet;
+use crate::simulator::Simulator;
+use std::fmt;
+use std::str::FromStr;
+
+const MOVES_PER_LINE: usize = 10;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetaData {
+    /// Names of P1 and P2
+    pub names: [Option<String>; 2],
+    pub date: Option<String>,
+    /// Random seeds used by P1 and P2
+    pub seeds: [Option<u128>; 2],
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRecord {
+    /// Initial positions of red ghosts of P1 and P2
+    pub red: [[Position; 4]; 2],
+    pub rules: RuleSet,
+    pub moves: Vec<Move>,
+    /// `None` if the game isn't finished
+    pub outcome: Option<Outcome>,
+    pub meta: MetaData,
+}
+
+impl GameRecord {
+    pub fn new(p1_red: [Position; 4], p2_red: [Position; 4]) -> Self {
+        GameRecord {
+            red: [p1_red, p2_red],
+            rules: RuleSet::default(),
+            moves: vec![],
+            outcome: None,
+            meta: MetaData::default(),
+        }
+    }
+    /// Replay the game and return the initial board and the boards after each move.
+    pub fn replay(&self) -> Result<Vec<Board>, ErrorKind> {
+        let mut sim = Simulator::with_rules(self.rules, self.red[0], self.red[1])?;
+        let mut boards = Vec::with_capacity(self.moves.len() + 1);
+        boards.push(sim.board().clone());
+        for &mov in &self.moves {
+            sim.apply(mov)?;
+            boards.push(sim.board().clone());
+        }
+        Ok(boards)
+    }
+}
+
+fn player_str(player: PlayerID) -> &'static str {
+    match player {
+        PlayerID::P1 => "P1",
+        PlayerID::P2 => "P2",
+    }
+}
+
+fn reason_str(reason: Reason) -> &'static str {
+    match reason {
+        Reason::Escape => "Escape",
+        Reason::AllBlueCaptured => "AllBlueCaptured",
+        Reason::AllRedTaken => "AllRedTaken",
+        Reason::TurnLimit => "TurnLimit",
+        Reason::IllegalMove => "IllegalMove",
+        Reason::NoLegalMove => "NoLegalMove",
+        Reason::Timeout => "Timeout",
+        Reason::Unknown => "Unknown",
+    }
+}
+
+fn parse_outcome(s: &str) -> Option<Outcome> {
+    let mut words = s.split_whitespace();
+    let winner = match words.next()? {
+        "P1" => Some(PlayerID::P1),
+        "P2" => Some(PlayerID::P2),
+        "Draw" => None,
+        _ => return None,
+    };
+    let reason = match words.next()? {
+        "Escape" => Reason::Escape,
+        "AllBlueCaptured" => Reason::AllBlueCaptured,
+        "AllRedTaken" => Reason::AllRedTaken,
+        "TurnLimit" => Reason::TurnLimit,
+        "IllegalMove" => Reason::IllegalMove,
+        "NoLegalMove" => Reason::NoLegalMove,
+        "Timeout" => Reason::Timeout,
+        "Unknown" => Reason::Unknown,
+        _ => return None,
+    };
+    if words.next().is_some() {
+        return None;
+    }
+    Some(Outcome { winner, reason })
+}
+
+fn write_tag(f: &mut fmt::Formatter, tag: &str, value: &str) -> fmt::Result {
+    write!(f, "[{} \"", tag)?;
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            write!(f, "\\")?;
+        }
+        write!(f, "{}", c)?;
+    }
+    writeln!(f, "\"]")
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let meta = &self.meta;
+        for (i, name) in meta.names.iter().enumerate() {
+            if let Some(name) = name {
+                write_tag(f, &format!("P{}", i + 1), name)?;
+            }
+        }
+        if let Some(date) = &meta.date {
+            write_tag(f, "Date", date)?;
+        }
+        for (i, seed) in meta.seeds.iter().enumerate() {
+            if let Some(seed) = seed {
+                write_tag(f, &format!("Seed{}", i + 1), &seed.to_string())?;
+            }
+        }
+        for (i, red) in self.red.iter().enumerate() {
+            let red: Vec<_> = red.iter().map(|p| p.to_string()).collect();
+            write_tag(f, &format!("Red{}", i + 1), &red.join(" "))?;
+        }
+        if self.rules != RuleSet::default() {
+            write_tag(f, "Rules", &self.rules.to_string())?;
+        }
+        if let Some(outcome) = self.outcome {
+            let winner = outcome.winner.map_or("Draw", player_str);
+            write_tag(f, "Result", &format!("{} {}", winner, reason_str(outcome.reason)))?;
+        }
+        for line in self.moves.chunks(MOVES_PER_LINE) {
+            let line: Vec<_> = line.iter().map(|m| m.to_string()).collect();
+            writeln!(f, "{}", line.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse `[Tag "value"]`
+fn parse_tag(line: &str) -> Option<(&str, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let space = inner.find(' ')?;
+    let (tag, value) = (&inner[..space], inner[space + 1..].trim());
+    let value = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut res = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => res.push(chars.next()?),
+            '"' => return None,
+            c => res.push(c),
+        }
+    }
+    Some((tag, res))
+}
+
+fn parse_red(s: &str) -> Option<[Position; 4]> {
+    let mut res = [Position::new(0, 0); 4];
+    let mut words = s.split_whitespace();
+    for pos in res.iter_mut() {
+        *pos = words.next()?.parse().ok()?;
+    }
+    if words.next().is_some() {
+        return None;
+    }
+    Some(res)
+}
+
+impl FromStr for GameRecord {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let mut red = [None, None];
+        let mut record = GameRecord::new([Position::new(0, 0); 4], [Position::new(0, 0); 4]);
+        for (i, line) in s.lines().enumerate() {
+            let err = |msg: &str| ParseError::Record(i + 1, msg.to_owned());
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if !line.starts_with('[') {
+                for mov in line.split_whitespace() {
+                    record.moves.push(mov.parse().map_err(|e: ParseError| err(&e.to_string()))?);
+                }
+                continue;
+            }
+            if !record.moves.is_empty() {
+                return Err(err("Tag after moves"));
+            }
+            let (tag, value) = parse_tag(line).ok_or_else(|| err("Invalid tag"))?;
+            let meta = &mut record.meta;
+            match tag {
+                "P1" => meta.names[0] = Some(value),
+                "P2" => meta.names[1] = Some(value),
+                "Date" => meta.date = Some(value),
+                "Seed1" | "Seed2" => {
+                    let seed = value.parse().map_err(|_| err("Invalid seed"))?;
+                    meta.seeds[if tag == "Seed1" { 0 } else { 1 }] = Some(seed);
+                }
+                "Red1" | "Red2" => {
+                    let pos = parse_red(&value).ok_or_else(|| err("Invalid red positions"))?;
+                    red[if tag == "Red1" { 0 } else { 1 }] = Some(pos);
+                }
+                "Rules" => {
+                    record.rules = value.parse().map_err(|e: ParseError| err(&e.to_string()))?;
+                }
+                "Result" => {
+                    let outcome = parse_outcome(&value).ok_or_else(|| err("Invalid result"))?;
+                    record.outcome = Some(outcome);
+                }
+                _ => return Err(err("Unknown tag")),
+            }
+        }
+        match red {
+            [Some(p1), Some(p2)] => record.red = [p1, p2],
+            [None, _] => return Err(ParseError::MissingTag("Red1".to_owned())),
+            [_, None] => return Err(ParseError::MissingTag("Red2".to_owned())),
+        }
+        Ok(record)
+    }
+}
+
+#[test]
+fn record_roundtrip() {
+    let p1_red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let p2_red = [
+        Position::new(1, 5),
+        Position::new(2, 5),
+        Position::new(3, 5),
+        Position::new(4, 5),
+    ];
+    let mut record = GameRecord::new(p1_red, p2_red);
+    record.meta.names = [Some("random".to_owned()), Some("say \"hi\"".to_owned())];
+    record.meta.seeds[1] = Some(42);
+    for m in "b2W b5N a2S b4N a3S b3N a4S c5N a5S d5N".split_whitespace() {
+        record.moves.push(m.parse().unwrap());
+    }
+    record.moves.push("a6W".parse().unwrap());
+    record.outcome = Some(Outcome::win(PlayerID::P1, Reason::Escape));
+    let text = record.to_string();
+    assert_eq!(text.parse(), Ok(record.clone()));
+    let boards = record.replay().unwrap();
+    assert_eq!(boards.len(), record.moves.len() + 1);
+    assert!(boards.last().unwrap()[Position::new(0, 5)].is_empty());
+    assert_eq!(
+        "[Red1 \"b1 c1 d1\"]".parse::<GameRecord>(),
+        Err(ParseError::Record(1, "Invalid red positions".to_owned()))
+    );
+    assert_eq!(
+        "[Red1 \"b1 c1 d1 e1\"]\nb2S".parse::<GameRecord>(),
+        Err(ParseError::MissingTag("Red2".to_owned()))
+    );
+    record.moves.push("a1N".parse().unwrap());
+    assert!(record.replay().is_err());
+    // a red ghost escapes in the teaching variant
+    let mut record = GameRecord::new(p1_red, p2_red);
+    record.rules = RuleSet::TEACHING;
+    for m in "e1E b5N f1S b4S f2S b5N f3S b4S f4S b5N f5S b4S f6E".split_whitespace() {
+        record.moves.push(m.parse().unwrap());
+    }
+    let text = record.to_string();
+    assert!(text.contains("[Rules \"a-\"]\n"));
+    assert_eq!(text.parse(), Ok(record.clone()));
+    let boards = record.replay().unwrap();
+    assert_eq!(boards.last().unwrap().rules(), &RuleSet::TEACHING);
+    assert!(boards.last().unwrap()[Position::new(5, 5)].is_empty());
+}
@@ -0,0 +1,189 @@
+//! Text renderers for boards
+//!
+//! ```text
+//!   a  b  c  d  e  f
+//! 1 .. Hr Fr Dr Br ..
+//! 2 .. Gb Eb Cb Ab ..
+//! ```
+use crate::board::*;
+use crate::player::PlayerID;
+use std::fmt;
+use yansi::Paint;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Style {
+    /// ID and color letters, e.g. `Hr` for P1's red ghost H, `a?` for P2's unknown ghost A
+    Ascii,
+    /// Colored triangles pointing to where the owner moves, with IDs
+    Unicode,
+    /// One character per cell in a single line, e.g. `.RRRR./.BBBB./....`
+    Compact,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Renderer {
+    pub style: Style,
+    /// Print column letters and row numbers. Ignored by `Style::Compact`.
+    pub labels: bool,
+    /// Draw the board as this player sees it, with their ghosts at the bottom.
+    /// `None` draws `y = 0` at the top.
+    pub perspective: Option<PlayerID>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::new(Style::Ascii)
+    }
+}
+
+impl Renderer {
+    pub fn new(style: Style) -> Self {
+        Renderer {
+            style,
+            labels: true,
+            perspective: None,
+        }
+    }
+    pub fn render<C: AsCell>(&self, board: &GenericBoard<C>) -> String {
+        self.display(board).to_string()
+    }
+    /// Wrap `board` so that it can be used with `{}`.
+    pub fn display<'a, C: AsCell>(&'a self, board: &'a GenericBoard<C>) -> impl fmt::Display + 'a {
+        Rendered {
+            renderer: self,
+            board,
+        }
+    }
+    fn flip(&self) -> bool {
+        self.perspective == Some(PlayerID::P1)
+    }
+    fn xs(&self) -> Vec<i8> {
+        let xs = 0..BOARD_WIDTH as i8;
+        if self.flip() {
+            xs.rev().collect()
+        } else {
+            xs.collect()
+        }
+    }
+    fn ys(&self) -> Vec<i8> {
+        let ys = 0..BOARD_HEIGHT as i8;
+        if self.flip() {
+            ys.rev().collect()
+        } else {
+            ys.collect()
+        }
+    }
+    fn write_cell(&self, f: &mut fmt::Formatter, cell: Cell) -> fmt::Result {
+        let o = match cell {
+            Cell::Owned(o) => o,
+            Cell::Empty => {
+                return match self.style {
+                    Style::Ascii => write!(f, ".."),
+                    Style::Unicode => write!(f, "· "),
+                    Style::Compact => write!(f, "."),
+                }
+            }
+        };
+        match self.style {
+            Style::Ascii => write!(f, "{}", o),
+            Style::Unicode => {
+                // P1 moves downwards unless the board is flipped
+                let down = (o.owner() == PlayerID::P1) != self.flip();
+                let glyph = if down { "▽" } else { "△" };
+                let id = o.id();
+                match o.ghost() {
+                    Ghost::Red => write!(f, "{}{}", Paint::red(glyph), Paint::red(id)),
+                    Ghost::Blue => write!(f, "{}{}", Paint::blue(glyph), Paint::blue(id)),
+                    Ghost::Unknown => write!(f, "{}{}", glyph, id),
+                }
+            }
+            Style::Compact => {
+                let c = match o.ghost() {
+                    Ghost::Red => 'R',
+                    Ghost::Blue => 'B',
+                    Ghost::Unknown => 'U',
+                };
+                match o.owner() {
+                    PlayerID::P1 => write!(f, "{}", c),
+                    PlayerID::P2 => write!(f, "{}", c.to_ascii_lowercase()),
+                }
+            }
+        }
+    }
+    fn write_board<C: AsCell>(
+        &self,
+        f: &mut fmt::Formatter,
+        board: &GenericBoard<C>,
+    ) -> fmt::Result {
+        let compact = self.style == Style::Compact;
+        if self.labels && !compact {
+            let cols: Vec<_> = self
+                .xs()
+                .iter()
+                .map(|&x| ((b'a' + x as u8) as char).to_string())
+                .collect();
+            writeln!(f, "  {}", cols.join("  "))?;
+        }
+        for (i, y) in self.ys().into_iter().enumerate() {
+            if compact && i > 0 {
+                write!(f, "/")?;
+            }
+            if self.labels && !compact {
+                write!(f, "{}", y + 1)?;
+            }
+            for x in self.xs() {
+                if !compact {
+                    write!(f, " ")?;
+                }
+                self.write_cell(f, board[Position::new(x, y)].to_cell())?;
+            }
+            if !compact {
+                writeln!(f)?;
+            }
+        }
+        if compact {
+            write!(f, " {}", board.next() as u8 + 1)?;
+        }
+        Ok(())
+    }
+}
+
+struct Rendered<'a, C> {
+    renderer: &'a Renderer,
+    board: &'a GenericBoard<C>,
+}
+
+impl<'a, C: AsCell> fmt::Display for Rendered<'a, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.renderer.write_board(f, self.board)
+    }
+}
+
+#[test]
+fn render_styles() {
+    let red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let board = Board::init_for_player(red, PlayerID::P1).unwrap();
+    let ascii = Renderer::default().render(&board);
+    assert!(ascii.starts_with("  a  b  c  d  e  f\n1 .. Hr Fr Dr Br ..\n"));
+    assert_eq!(ascii, format!("{:?}", board));
+    assert!(!ascii.contains('\x1b'));
+    let flipped = Renderer {
+        labels: false,
+        perspective: Some(PlayerID::P1),
+        ..Renderer::default()
+    };
+    assert!(flipped
+        .render(&board)
+        .ends_with(" .. Ab Cb Eb Gb ..\n .. Br Dr Fr Hr ..\n"));
+    let compact = Renderer::new(Style::Compact).render(&board);
+    assert_eq!(compact, ".RRRR./.BBBB./....../....../.uuuu./.uuuu. 1");
+    let unicode = Renderer::new(Style::Unicode).render(&board);
+    assert!(unicode.starts_with("  a  b  c  d  e  f\n1 ·  \x1b[31m▽\x1b[0m\x1b[31mH\x1b[0m"));
+    assert!(unicode.contains(" \x1b[34m▽\x1b[0m\x1b[34mA\x1b[0m · \n"));
+    assert!(unicode.contains("5 ·  △A △C △E △G · \n"));
+}
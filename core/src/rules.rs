@@ -0,0 +1,84 @@
+//! Rule variants
+//!
+//! The 6x6 board, the initial areas and the numbers of ghosts (`NUM_RED` red and
+//! `NUM_BLUE` blue per player) are fixed: cell arrays, Zobrist keys, bitboards
+//! and setups are all sized for them. The rules which can vary are in `RuleSet`.
+use crate::board::Ghost;
+use crate::error::ParseError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Which ghosts may leave the board from the escape squares
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EscapeRule {
+    /// Only blue ghosts, as in standard Geister
+    BlueOnly,
+    /// Any ghost, e.g. for teaching
+    AnyColor,
+}
+
+/// Displayed as `b300`: `b`(blue only) or `a`(any color) followed by the turn
+/// limit, or `-` for no limit
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuleSet {
+    pub escape: EscapeRule,
+    /// The game is a draw after this number of plies. `None` means no limit.
+    pub turn_limit: Option<u32>,
+}
+
+impl RuleSet {
+    /// Standard Geister with the turn limit of GPW
+    pub const STANDARD: RuleSet = RuleSet {
+        escape: EscapeRule::BlueOnly,
+        turn_limit: Some(300),
+    };
+    /// Any ghost can escape and there's no turn limit.
+    pub const TEACHING: RuleSet = RuleSet {
+        escape: EscapeRule::AnyColor,
+        turn_limit: None,
+    };
+    pub fn can_escape(&self, ghost: Ghost) -> bool {
+        match self.escape {
+            EscapeRule::BlueOnly => ghost == Ghost::Blue,
+            EscapeRule::AnyColor => true,
+        }
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet::STANDARD
+    }
+}
+
+impl fmt::Display for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.escape {
+            EscapeRule::BlueOnly => write!(f, "b")?,
+            EscapeRule::AnyColor => write!(f, "a")?,
+        }
+        match self.turn_limit {
+            Some(limit) => write!(f, "{}", limit),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+impl FromStr for RuleSet {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let err = || ParseError::Rules(s.to_owned());
+        let escape = match s.get(..1) {
+            Some("b") => EscapeRule::BlueOnly,
+            Some("a") => EscapeRule::AnyColor,
+            _ => return Err(err()),
+        };
+        let turn_limit = match &s[1..] {
+            "-" => None,
+            limit => Some(limit.parse().map_err(|_| err())?),
+        };
+        Ok(RuleSet { escape, turn_limit })
+    }
+}
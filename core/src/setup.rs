@@ -0,0 +1,107 @@
+//! Starting positions
+use crate::board::*;
+use crate::error::ErrorKind;
+use crate::outcome::NUM_RED;
+use crate::player::PlayerID;
+use crate::rules::RuleSet;
+
+/// Check that `red` is a valid placement of red ghosts of `player`: `NUM_RED`
+/// distinct positions from `PlayerID::init_pos`.
+pub fn check_red(player: PlayerID, red: &[Position]) -> Result<(), ErrorKind> {
+    for (i, &pos) in red.iter().enumerate() {
+        if pos.in_init_area() != Some(player) {
+            return ErrorKind::OutOfInitArea(player, pos).into();
+        }
+        if red[..i].contains(&pos) {
+            return ErrorKind::DuplicatePlacement(player, pos).into();
+        }
+    }
+    if red.len() != usize::from(NUM_RED) {
+        // distinct positions in the initial area, so at most 8
+        return ErrorKind::RedCount(player, red.len() as u8).into();
+    }
+    Ok(())
+}
+
+/// Builds a full-information starting board from both players' red ghosts,
+/// for referees and self-play.
+#[derive(Clone, Debug, Default)]
+pub struct SetupBuilder {
+    red: [Vec<Position>; 2],
+    rules: RuleSet,
+}
+
+impl SetupBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Positions of the red ghosts of `player`. The others are blue.
+    pub fn red(mut self, player: PlayerID, red: &[Position]) -> Self {
+        self.red[player as usize] = red.to_vec();
+        self
+    }
+    pub fn rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+    pub fn build(&self) -> Result<Board, ErrorKind> {
+        let mut board = Board::default();
+        for &player in &[PlayerID::P1, PlayerID::P2] {
+            let red = &self.red[player as usize];
+            check_red(player, red)?;
+            player.init(|pos, id| {
+                let ghost = if red.contains(&pos) {
+                    Ghost::Red
+                } else {
+                    Ghost::Blue
+                };
+                board.set(pos, Cell::owned(ghost, player, id));
+            });
+        }
+        board.set_rules(self.rules);
+        Ok(board)
+    }
+}
+
+#[test]
+fn setup_errors() {
+    let pos =
+        |s: &str| -> Vec<Position> { s.split_whitespace().map(|p| p.parse().unwrap()).collect() };
+    let p1 = pos("b1 c1 d2 e2");
+    let board = SetupBuilder::new()
+        .red(PlayerID::P1, &p1)
+        .red(PlayerID::P2, &pos("b5 c5 d6 e6"))
+        .build()
+        .unwrap();
+    assert!(!board.is_view_for(PlayerID::P1));
+    assert_eq!(
+        board.view_for(PlayerID::P1),
+        Board::init_for_player([p1[0], p1[1], p1[2], p1[3]], PlayerID::P1).unwrap()
+    );
+    let build = |p2: &str| {
+        SetupBuilder::new()
+            .red(PlayerID::P1, &p1)
+            .red(PlayerID::P2, &pos(p2))
+            .build()
+    };
+    assert_eq!(build("b5 c5 d6"), Err(ErrorKind::RedCount(PlayerID::P2, 3)));
+    assert_eq!(
+        build("b5 c5 d6 e6 e5"),
+        Err(ErrorKind::RedCount(PlayerID::P2, 5))
+    );
+    assert_eq!(
+        build("b5 c5 d6 a6"),
+        Err(ErrorKind::OutOfInitArea(PlayerID::P2, Position::new(0, 5)))
+    );
+    assert_eq!(
+        build("b5 c5 b5 e6"),
+        Err(ErrorKind::DuplicatePlacement(
+            PlayerID::P2,
+            Position::new(1, 4)
+        ))
+    );
+    assert_eq!(
+        SetupBuilder::new().build(),
+        Err(ErrorKind::RedCount(PlayerID::P1, 0))
+    );
+}
@@ -1,52 +1,472 @@
 use crate::{board::*, player::*};
 use crate::error::ErrorKind;
+use crate::event::GameEvent;
+use crate::observer::GameObserver;
+use crate::outcome::{Outcome, Reason};
+use crate::rules::RuleSet;
+use crate::setup::{check_red, SetupBuilder};
 
-#[derive(Clone, )]
+/// Referee which owns the full-information board.
 pub struct Simulator {
     board: Board,
-    next: PlayerID,
+    outcome: Option<Outcome>,
+    observers: Vec<Box<dyn GameObserver>>,
 }
 
-pub enum Transition {
-    Lost(OwnedCell),
-    End(PlayerID),
-    None,
+/// Clones have no observers, so that they can be used for search.
+impl Clone for Simulator {
+    fn clone(&self) -> Self {
+        Simulator {
+            board: self.board.clone(),
+            outcome: self.outcome,
+            observers: vec![],
+        }
+    }
 }
 
 impl Simulator {
-    pub fn transit(&mut self, next: Board) -> Transition {
-        let diffs = self.board.diff(&next);
-        self.board = next;
-        self.next = self.next.rev();
-        for diff in diffs {
-            let Diff {
-                pos,
-                before,
-                after,
-            } = diff;
-            match before {
-                Cell::Owned(before) => match after {
-                    Cell::Owned(_) => return Transition::Lost(before),
-                    Cell::Empty => {
-                        let owner = before.owner();
-                        if before.ghost() == Ghost::Blue && pos.is_escape(owner) {
-                            return Transition::End(owner);
-                        }
-                    }
-                }
-                Cell::Empty => continue,
-            }
+    /// Start a game from both players' red ghost positions.
+    pub fn new(p1_red: [Position; 4], p2_red: [Position; 4]) -> Result<Self, ErrorKind> {
+        Self::with_rules(RuleSet::default(), p1_red, p2_red)
+    }
+    /// Same as `new`, but plays by `rules`.
+    pub fn with_rules(
+        rules: RuleSet,
+        p1_red: [Position; 4],
+        p2_red: [Position; 4],
+    ) -> Result<Self, ErrorKind> {
+        let board = SetupBuilder::new()
+            .red(PlayerID::P1, &p1_red)
+            .red(PlayerID::P2, &p2_red)
+            .rules(rules)
+            .build()?;
+        Ok(Simulator {
+            board,
+            outcome: None,
+            observers: vec![],
+        })
+    }
+    /// Add an observer, which is told that the game starts from the current board.
+    pub fn add_observer(&mut self, mut observer: Box<dyn GameObserver>) {
+        observer.on_start(&self.board);
+        self.observers.push(observer);
+    }
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+    pub fn next(&self) -> PlayerID {
+        self.board.next()
+    }
+    /// The result of the game, if it's over
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+    /// The board as `player` sees it: colors of the opponent's ghosts are hidden.
+    pub fn view(&self, player: PlayerID) -> Board {
+        self.board.view_for(player)
+    }
+    /// Check whether the player to move has any legal move.
+    pub fn can_continue(&self) -> bool {
+        !self.board.legal_moves(self.next()).is_empty()
+    }
+    /// Apply the move of the next player which turns the board into `next`.
+    pub fn transit(&mut self, next: &Board) -> Result<Vec<GameEvent>, ErrorKind> {
+        let undo = self.board.infer_move(next, self.next())?;
+        self.apply(undo.mov)
+    }
+    /// Validate `mov` as a move of the next player and apply it.
+    /// Returns the event of the move, followed by `GameOver` if the game ends.
+    pub fn apply(&mut self, mov: Move) -> Result<Vec<GameEvent>, ErrorKind> {
+        if self.outcome.is_some() {
+            return ErrorKind::GameOver.into();
         }
-        Transition::None
+        let player = self.next();
+        let event = self.board.transit(mov)?;
+        self.outcome = match event {
+            GameEvent::Escaped { ghost, .. } => Some(Outcome::win(ghost.owner(), Reason::Escape)),
+            _ => self.board.captured().outcome(),
+        };
+        if self.outcome.is_none() && self.board.is_turn_limit() {
+            self.outcome = Some(Outcome::draw(Reason::TurnLimit));
+        }
+        let mut events = vec![event];
+        events.extend(self.outcome.map(GameEvent::GameOver));
+        notify(&mut self.observers, player, mov, &events, &self.board, self.outcome);
+        Ok(events)
     }
-    pub fn play<F>(&mut self, policy: F) -> Transition
+    /// Finish the game because `loser` broke the rules.
+    pub fn resign(&mut self, loser: PlayerID, reason: Reason) -> Result<Outcome, ErrorKind> {
+        if self.outcome.is_some() {
+            return ErrorKind::GameOver.into();
+        }
+        Ok(self.finish(Outcome::lose(loser, reason)))
+    }
+    fn finish(&mut self, outcome: Outcome) -> Outcome {
+        self.outcome = Some(outcome);
+        end(&mut self.observers, outcome)
+    }
+    /// Ask `policy` for a move of the next player and apply it.
+    /// `policy` receives only the view of the next player.
+    pub fn play<F>(&mut self, policy: F) -> Result<Vec<GameEvent>, ErrorKind>
     where
         F: FnOnce(&Board, PlayerID) -> Move,
     {
-        let mov = policy(&self.board, self.next);
-        Transition::None
+        let next = self.next();
+        let view = self.view(next);
+        let mov = policy(&view, next);
+        self.apply(mov)
+    }
+    /// Play a whole game between `p1` and `p2` and return the outcome.
+    /// A player which fails to return a valid setup or a legal move, or has no legal
+    /// move to play, loses the game. It's a draw if both setups are invalid.
+    pub fn playout<P1, P2>(p1: &mut P1, p2: &mut P2) -> Outcome
+    where
+        P1: Player,
+        P2: Player,
+    {
+        Self::playout_with(RuleSet::default(), &mut [], p1, p2)
+    }
+    /// Same as `playout`, but plays by `rules` and reports to `observers`.
+    pub fn playout_with<P1, P2>(
+        rules: RuleSet,
+        observers: &mut [Box<dyn GameObserver>],
+        p1: &mut P1,
+        p2: &mut P2,
+    ) -> Outcome
+    where
+        P1: Player,
+        P2: Player,
+    {
+        let valid = |player, red: &[Position; 4]| check_red(player, red).is_ok();
+        let p1_red = p1.init(PlayerID::P1).ok().filter(|red| valid(PlayerID::P1, red));
+        let p2_red = p2.init(PlayerID::P2).ok().filter(|red| valid(PlayerID::P2, red));
+        let outcome = match (p1_red, p2_red) {
+            (Some(p1_red), Some(p2_red)) => {
+                let mut sim = Simulator::with_rules(rules, p1_red, p2_red)
+                    .expect("both setups are checked by check_red");
+                sim.play_to_end(p1, p2, observers)
+            }
+            // a game which ends before it starts still ends for observers
+            (None, None) => end(observers, Outcome::draw(Reason::IllegalMove)),
+            (None, _) => end(observers, Outcome::lose(PlayerID::P1, Reason::IllegalMove)),
+            (_, None) => end(observers, Outcome::lose(PlayerID::P2, Reason::IllegalMove)),
+        };
+        p1.close(outcome.winner == Some(PlayerID::P1));
+        p2.close(outcome.winner == Some(PlayerID::P2));
+        outcome
+    }
+    /// Play the game to the end, reporting it to `observers` as well as to the own ones.
+    fn play_to_end<P1, P2>(
+        &mut self,
+        p1: &mut P1,
+        p2: &mut P2,
+        observers: &mut [Box<dyn GameObserver>],
+    ) -> Outcome
+    where
+        P1: Player,
+        P2: Player,
+    {
+        for observer in observers.iter_mut() {
+            observer.on_start(&self.board);
+        }
+        loop {
+            if let Some(outcome) = self.outcome {
+                return outcome;
+            }
+            let next = self.next();
+            if !self.can_continue() {
+                let outcome = self.finish(Outcome::lose(next, Reason::NoLegalMove));
+                return end(observers, outcome);
+            }
+            let view = self.view(next);
+            debug_assert!(view.is_view_for(next));
+            let mov = match next {
+                PlayerID::P1 => p1.step(view).ok(),
+                PlayerID::P2 => p2.step(view).ok(),
+            };
+            match mov.map(|mov| (mov, self.apply(mov))) {
+                Some((mov, Ok(events))) => {
+                    notify(observers, next, mov, &events, &self.board, self.outcome)
+                }
+                _ => {
+                    let outcome = self.finish(Outcome::lose(next, Reason::IllegalMove));
+                    return end(observers, outcome);
+                }
+            }
+        }
+    }
+}
+
+/// Tell `observers` that `player` played `mov`, and that the game ended if `outcome` is set.
+fn notify(
+    observers: &mut [Box<dyn GameObserver>],
+    player: PlayerID,
+    mov: Move,
+    events: &[GameEvent],
+    board: &Board,
+    outcome: Option<Outcome>,
+) {
+    for observer in observers.iter_mut() {
+        observer.on_move(player, mov, events, board);
+        if let Some(outcome) = outcome {
+            observer.on_end(outcome);
+        }
+    }
+}
+
+fn end(observers: &mut [Box<dyn GameObserver>], outcome: Outcome) -> Outcome {
+    for observer in observers.iter_mut() {
+        observer.on_end(outcome);
+    }
+    outcome
+}
+
+#[test]
+fn play_escape() {
+    let p1_red = [
+        Position::new(1, 0),
+        Position::new(2, 0),
+        Position::new(3, 0),
+        Position::new(4, 0),
+    ];
+    let p2_red = [
+        Position::new(1, 5),
+        Position::new(2, 5),
+        Position::new(3, 5),
+        Position::new(4, 5),
+    ];
+    let mut sim = Simulator::new(p1_red, p2_red).unwrap();
+    let view = sim.view(PlayerID::P1);
+    assert_eq!(view[Position::new(1, 4)], Cell::owned(Ghost::Unknown, PlayerID::P2, GhostID::A));
+    // P2 can't move first
+    let p2_mov = Move {
+        pos: Position::new(1, 4),
+        direction: Direction::Up,
+    };
+    assert_eq!(sim.apply(p2_mov), Err(ErrorKind::NotYourPiece(p2_mov)));
+    assert_eq!(sim.next(), PlayerID::P1);
+    // P1's blue ghost at (1, 1) goes to the corner (0, 5) and escapes
+    let mut script = vec![
+        Move {
+            pos: Position::new(1, 1),
+            direction: Direction::Left,
+        },
+        Move {
+            pos: Position::new(0, 1),
+            direction: Direction::Down,
+        },
+        Move {
+            pos: Position::new(0, 2),
+            direction: Direction::Down,
+        },
+        Move {
+            pos: Position::new(0, 3),
+            direction: Direction::Down,
+        },
+        Move {
+            pos: Position::new(0, 4),
+            direction: Direction::Down,
+        },
+        Move {
+            pos: Position::new(0, 5),
+            direction: Direction::Left,
+        },
+    ]
+    .into_iter();
+    let mut p2_script = [(4, 4), (3, 4), (2, 4), (1, 4), (1, 5)]
+        .iter()
+        .map(|&(x, y)| Move {
+            pos: Position::new(x, y),
+            direction: Direction::Up,
+        });
+    loop {
+        let events = sim.play(|_, _| script.next().unwrap()).unwrap();
+        if let Some(&GameEvent::GameOver(outcome)) = events.last() {
+            assert_eq!(outcome.winner, Some(PlayerID::P1));
+            assert!(matches!(events[0], GameEvent::Escaped { .. }));
+            break;
+        }
+        let mov = p2_script.next().unwrap();
+        sim.play(|_, p| {
+            assert_eq!(p, PlayerID::P2);
+            mov
+        })
+        .unwrap();
     }
-    pub fn playout(start: PlayerID) -> Result<PlayerID, ErrorKind> {        
-        Ok(start)
+    assert!(sim.board()[Position::new(0, 5)].is_empty());
+    assert_eq!(sim.outcome(), Some(Outcome::win(PlayerID::P1, Reason::Escape)));
+    assert_eq!(sim.apply(p2_mov), Err(ErrorKind::GameOver));
+}
+
+#[test]
+fn rule_variants() {
+    let red = |s: &str| {
+        let mut res = [Position::new(0, 0); 4];
+        for (pos, p) in res.iter_mut().zip(s.split_whitespace()) {
+            *pos = p.parse().unwrap();
+        }
+        res
+    };
+    let (p1_red, p2_red) = (red("b2 c2 d2 e2"), red("b6 c6 d6 e6"));
+    let p2_moves = ["e5N".parse().unwrap(), "e4S".parse().unwrap()];
+    // a red ghost escapes
+    let mut sim = Simulator::with_rules(RuleSet::TEACHING, p1_red, p2_red).unwrap();
+    for (i, mov) in "b2W a2S a3S a4S a5S a6W".split_whitespace().enumerate() {
+        assert_eq!(sim.outcome(), None);
+        sim.apply(mov.parse().unwrap()).unwrap();
+        if i < 5 {
+            sim.apply(p2_moves[i % 2]).unwrap();
+        }
+    }
+    assert_eq!(sim.outcome(), Some(Outcome::win(PlayerID::P1, Reason::Escape)));
+    let rules = RuleSet {
+        turn_limit: Some(4),
+        ..RuleSet::STANDARD
+    };
+    let mut sim = Simulator::with_rules(rules, p1_red, p2_red).unwrap();
+    for mov in "b2W e5N a2S".split_whitespace() {
+        sim.apply(mov.parse().unwrap()).unwrap();
+    }
+    assert_eq!(sim.outcome(), None);
+    sim.apply(p2_moves[1]).unwrap();
+    assert_eq!(sim.outcome(), Some(Outcome::draw(Reason::TurnLimit)));
+    assert_eq!(sim.apply("a3S".parse().unwrap()), Err(ErrorKind::GameOver));
+}
+
+#[test]
+fn observe_game() {
+    use std::{cell::RefCell, rc::Rc};
+    #[derive(Default)]
+    struct Log {
+        start: Option<Board>,
+        moves: Vec<(PlayerID, Move)>,
+        events: usize,
+        outcome: Option<Outcome>,
+    }
+    impl GameObserver for Log {
+        fn on_start(&mut self, board: &Board) {
+            self.start = Some(board.clone());
+        }
+        fn on_move(&mut self, player: PlayerID, mov: Move, events: &[GameEvent], _: &Board) {
+            self.moves.push((player, mov));
+            self.events += events.len();
+        }
+        fn on_end(&mut self, outcome: Outcome) {
+            self.outcome = Some(outcome);
+        }
+    }
+    let red = |s: &str| {
+        let mut res = [Position::new(0, 0); 4];
+        for (pos, p) in res.iter_mut().zip(s.split_whitespace()) {
+            *pos = p.parse().unwrap();
+        }
+        res
+    };
+    let rules = RuleSet {
+        turn_limit: Some(4),
+        ..RuleSet::STANDARD
+    };
+    let mut sim = Simulator::with_rules(rules, red("b1 c1 d1 e1"), red("b6 c6 d6 e6")).unwrap();
+    let log = Rc::new(RefCell::new(Log::default()));
+    sim.add_observer(Box::new(log.clone()));
+    for m in "b2W e5N a2S e4S".split_whitespace() {
+        sim.apply(m.parse().unwrap()).unwrap();
+    }
+    let log = log.borrow();
+    assert_eq!(log.start.as_ref().map(|b| b.plies()), Some(0));
+    assert_eq!(log.moves.len(), 4);
+    assert_eq!(log.moves[1], (PlayerID::P2, "e5N".parse().unwrap()));
+    // the last move ends the game
+    assert_eq!(log.events, 5);
+    assert_eq!(log.outcome, Some(Outcome::draw(Reason::TurnLimit)));
+    drop(log);
+    assert_eq!(sim.resign(PlayerID::P1, Reason::Timeout), Err(ErrorKind::GameOver));
+    assert_eq!(sim.outcome(), Some(Outcome::draw(Reason::TurnLimit)));
+}
+
+#[test]
+fn playout_scripted() {
+    use std::{cell::RefCell, rc::Rc};
+    #[derive(Default)]
+    struct Ended(Option<Outcome>);
+    impl GameObserver for Ended {
+        fn on_end(&mut self, outcome: Outcome) {
+            self.0 = Some(outcome);
+        }
+    }
+    struct Script {
+        id: PlayerID,
+        red: &'static str,
+        moves: Vec<Move>,
+        board: Board,
+        victory: Option<bool>,
+    }
+    impl Script {
+        fn new(id: PlayerID, red: &'static str, moves: &str) -> Self {
+            // popped from the back
+            let mut moves: Vec<Move> = moves
+                .split_whitespace()
+                .map(|m| m.parse().unwrap())
+                .collect();
+            moves.reverse();
+            Script {
+                id,
+                red,
+                moves,
+                board: Board::default(),
+                victory: None,
+            }
+        }
+    }
+    impl Player for Script {
+        type Error = ();
+        fn id(&self) -> PlayerID {
+            self.id
+        }
+        fn init(&mut self, _: PlayerID) -> Result<[Position; 4], ()> {
+            let mut res = [Position::new(0, 0); 4];
+            for (pos, p) in res.iter_mut().zip(self.red.split_whitespace()) {
+                *pos = p.parse().unwrap();
+            }
+            Ok(res)
+        }
+        fn board(&self) -> &Board {
+            &self.board
+        }
+        fn step(&mut self, board: Board) -> Result<Move, ()> {
+            self.board = board;
+            self.moves.pop().ok_or(())
+        }
+        fn close(&mut self, victory: bool) {
+            self.victory = Some(victory);
+        }
     }
+    // P1's blue ghost escapes from a6
+    let mut p1 = Script::new(PlayerID::P1, "b1 c1 d1 e1", "b2W a2S a3S a4S a5S a6W");
+    let mut p2 = Script::new(PlayerID::P2, "b6 c6 d6 e6", "e5N e4S e5N e4S e5N");
+    let outcome = Simulator::playout(&mut p1, &mut p2);
+    assert_eq!(outcome, Outcome::win(PlayerID::P1, Reason::Escape));
+    assert_eq!((p1.victory, p2.victory), (Some(true), Some(false)));
+    assert!(p1.board.is_view_for(PlayerID::P1));
+    // P2 runs out of moves and `step` fails
+    let ended = Rc::new(RefCell::new(Ended::default()));
+    let mut observers: Vec<Box<dyn GameObserver>> = vec![Box::new(ended.clone())];
+    let mut p1 = Script::new(PlayerID::P1, "b1 c1 d1 e1", "b2W a2S a3S");
+    let mut p2 = Script::new(PlayerID::P2, "b6 c6 d6 e6", "e5N e4S");
+    let outcome = Simulator::playout_with(RuleSet::default(), &mut observers, &mut p1, &mut p2);
+    assert_eq!(outcome, Outcome::lose(PlayerID::P2, Reason::IllegalMove));
+    assert_eq!((p1.victory, p2.victory), (Some(true), Some(false)));
+    assert_eq!(ended.borrow().0, Some(outcome));
+    // P1 places two red ghosts on b1, and observers still see the end of the game
+    let mut p1 = Script::new(PlayerID::P1, "b1 b1 d1 e1", "");
+    let mut p2 = Script::new(PlayerID::P2, "b6 c6 d6 e6", "");
+    let outcome = Simulator::playout_with(RuleSet::default(), &mut observers, &mut p1, &mut p2);
+    assert_eq!(outcome, Outcome::lose(PlayerID::P1, Reason::IllegalMove));
+    assert_eq!((p1.victory, p2.victory), (Some(false), Some(true)));
+    assert_eq!(ended.borrow().0, Some(outcome));
+    let mut p2 = Script::new(PlayerID::P2, "b1 c1 d1 e1", "");
+    let outcome = Simulator::playout_with(RuleSet::default(), &mut observers, &mut p1, &mut p2);
+    assert_eq!(outcome, Outcome::draw(Reason::IllegalMove));
+    assert_eq!((p1.victory, p2.victory), (Some(false), Some(false)));
+    assert_eq!(ended.borrow().0, Some(outcome));
 }
@@ -0,0 +1,38 @@
+//! Keys for Zobrist hashing of boards
+use crate::board::{BOARD_HEIGHT, BOARD_WIDTH};
+
+/// Number of distinct values of `OwnedCell`
+const NUM_CELLS: usize = 64;
+
+/// splitmix64, used to fill the key table at compile time
+const fn splitmix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+const fn gen_keys() -> [[u64; NUM_CELLS]; BOARD_HEIGHT * BOARD_WIDTH] {
+    let mut keys = [[0; NUM_CELLS]; BOARD_HEIGHT * BOARD_WIDTH];
+    let mut i = 0;
+    while i < BOARD_HEIGHT * BOARD_WIDTH {
+        let mut j = 0;
+        while j < NUM_CELLS {
+            keys[i][j] = splitmix((i * NUM_CELLS + j) as u64);
+            j += 1;
+        }
+        i += 1;
+    }
+    keys
+}
+
+static KEYS: [[u64; NUM_CELLS]; BOARD_HEIGHT * BOARD_WIDTH] = gen_keys();
+
+/// Xor-ed when player 2 is to move
+pub(crate) const SIDE: u64 = splitmix(u64::MAX);
+
+/// Key of the ghost encoded as `cell` at board index `idx`
+#[inline(always)]
+pub(crate) fn key(idx: usize, cell: u8) -> u64 {
+    KEYS[idx][usize::from(cell)]
+}
@@ -1,5 +1,9 @@
 use geister_core::{
-    board::{Board, Cell, Direction, Ghost, GhostID, Move, Position, BOARD_HEIGHT, BOARD_WIDTH},
+    board::{Board, Cell, Ghost, GhostID, Move, Position},
+    error::ErrorKind,
+    event::GameEvent,
+    observer::GameObserver,
+    outcome::{Captured, Outcome, Reason},
     player::{Player, PlayerID},
 };
 use std::io::{self, prelude::*};
@@ -15,7 +19,7 @@ impl GpwPosition for Position {
     fn init_pos(&self) -> Option<u8> {
         let Position { x, y } = *self;
         let offset = match x {
-            x if 1 <= x && x <= 4 => x as u8 - 1,
+            x if (1..=4).contains(&x) => x as u8 - 1,
             _ => return None,
         };
         Some(match y {
@@ -26,9 +30,9 @@ impl GpwPosition for Position {
             _ => return None,
         })
     }
-    fn reverse_by_id(self, player: PlayerID) -> Self {        
+    fn reverse_by_id(self, player: PlayerID) -> Self {
         if player == PlayerID::P1 {
-            Position::new(BOARD_WIDTH as i8 - self.x - 1, BOARD_HEIGHT as i8 - self.y - 1)
+            self.flipped()
         } else {
             self
         }
@@ -41,17 +45,7 @@ pub trait GpwMove: Sized {
 
 impl GpwMove for Move {
     fn to_gpw(self, id: GhostID, player: PlayerID) -> String {
-        let d = match player {
-            PlayerID::P1 => self.direction.rev(),
-            PlayerID::P2 => self.direction,
-        };
-        let d = match d {
-            Direction::Up => 'N',
-            Direction::Down => 'S',
-            Direction::Left => 'W',
-            Direction::Right => 'E',
-        };
-        format!("MOV:{},{}\r\n", id, d)
+        format!("MOV:{},{}\r\n", id, self.perspective(player).direction)
     }
 }
 
@@ -69,45 +63,50 @@ where
 }
 
 impl GpwBoard for Board {
+    /// Ghosts on `99` are captured: `r` and `b` are their revealed colors.
+    /// Other coordinates out of the board, e.g. of escaped ghosts, are skipped.
     fn from_gpw<C>(s: &str, player: PlayerID) -> Result<Self, Error<C>> {
         let mut board = Board::default();
-        for i in 0..8 {
+        for i in 0..16 {
             let start = 3 * i;
             let x: i8 = read1(s, start)?;
             let y: i8 = read1(s, start + 1)?;
-            let ghost = match read1::<char, _>(s, start + 2)? {
-                'R' => Ghost::Red,
-                'B' => Ghost::Blue,
+            let owner = if i < 8 { player } else { player.rev() };
+            let id = GhostID::from_u8((i % 8) as u8).unwrap();
+            let ghost = match (read1::<char, _>(s, start + 2)?, i < 8) {
+                ('R', true) => Ghost::Red,
+                ('B', true) => Ghost::Blue,
+                ('u', false) => Ghost::Unknown,
+                (c @ 'r', _) | (c @ 'b', _) if (x, y) == (9, 9) => {
+                    let ghost = if c == 'r' { Ghost::Red } else { Ghost::Blue };
+                    let captured = board.captured_mut();
+                    captured.set_count(owner, ghost, captured.count(owner, ghost) + 1);
+                    continue;
+                }
                 _ => continue,
             };
-            board[Position::new(x, y).reverse_by_id(player)] =
-                Cell::owned(ghost, player, GhostID::from_u8(i as u8).unwrap());
-        }
-        for i in 0..8 {
-            let start = 3 * (i + 8);
-            let x: i8 = read1(s, start)?;
-            let y: i8 = read1(s, start + 1)?;
-            let ghost = match read1::<char, _>(s, start + 2)? {
-                'u' => Ghost::Unknown,
-                _ => continue,
-            };
-            board[Position::new(x, y).reverse_by_id(player)] =
-                Cell::owned(ghost, player.rev(), GhostID::from_u8(i as u8).unwrap());
+            let pos = Position::new(x, y);
+            if !pos.is_valid() {
+                continue;
+            }
+            board.set(pos.reverse_by_id(player), Cell::owned(ghost, owner, id));
         }
+        board.set_next(player);
         Ok(board)
     }
 }
 
 pub trait GpwPlayer: Player {
-    fn gpw_step(&mut self, board: &str) -> Result<String, Error<Self::Error>> {
-        let board = Board::from_gpw(board, self.id())?;
-        let mov = self.step(board).map_err(Error::Agent)?;
+    /// Ask for a move on `board` and apply it to `board`.
+    /// Returns the message to send, the move and what it did.
+    fn gpw_step(
+        &mut self,
+        board: &mut Board,
+    ) -> Result<(String, Move, GameEvent), Error<Self::Error>> {
+        let mov = self.step(board.clone()).map_err(Error::Agent)?;
         println!("{:?}", mov);
-        let cell = self.board()[mov.pos];
-        match cell {
-            Cell::Owned(o) => Ok(mov.to_gpw(o.id(), self.id())),
-            Cell::Empty => Err(Error::InvalidMove(mov)),
-        }
+        let undo = board.apply(mov).map_err(Error::Rule)?;
+        Ok((mov.to_gpw(undo.moved.id(), self.id()), mov, undo.event()))
     }
 }
 
@@ -117,7 +116,7 @@ impl<P: Player> GpwPlayer for P {}
 pub enum Error<C> {
     Agent(C),
     Io(io::Error),
-    InvalidMove(Move),
+    Rule(ErrorKind),
     Mismatch(String),
     ParseError(String),
 }
@@ -162,7 +161,58 @@ fn port(id: PlayerID) -> u16 {
     }
 }
 
+/// Guess the outcome from the last message. GPW doesn't say why the game ended,
+/// so the reason is derived from `captured` of the last board, or is `Escape` if
+/// `escaped` tells that a ghost escaped. Otherwise it's `Unknown`.
+fn gpw_outcome(
+    head: &str,
+    captured: &Captured,
+    escaped: bool,
+    player: PlayerID,
+) -> Option<Outcome> {
+    let winner = match head {
+        "WON:" => player,
+        "LST:" => player.rev(),
+        "DRW:" => return Some(Outcome::draw(Reason::TurnLimit)),
+        _ => return None,
+    };
+    Some(match captured.outcome() {
+        Some(outcome) if outcome.winner == Some(winner) => outcome,
+        _ if escaped => Outcome::win(winner, Reason::Escape),
+        _ => Outcome::win(winner, Reason::Unknown),
+    })
+}
+
+/// Whether a ghost in a GPW board is on `88`, out of the board after escaping
+fn gpw_escaped(s: &str) -> bool {
+    (0..16).any(|i| s.get(3 * i..3 * i + 2) == Some("88"))
+}
+
+fn notify(
+    observers: &mut [Box<dyn GameObserver>],
+    player: PlayerID,
+    mov: Move,
+    event: GameEvent,
+    board: &Board,
+) {
+    for observer in observers.iter_mut() {
+        observer.on_move(player, mov, &[event], board);
+    }
+}
+
 pub fn run_client<C: GpwPlayer>(client: &mut C, addr: IpAddr) -> Result<(), Error<C::Error>> {
+    run_client_with(client, addr, &mut [])
+}
+
+/// Same as `run_client`, but reports the game to `observers` with the boards of the client.
+/// GPW sends only boards, so the moves of the opponent are inferred from them and the
+/// ones which can't be inferred are not reported.
+/// As P2, the game starts from the board after the first move of P1.
+pub fn run_client_with<C: GpwPlayer>(
+    client: &mut C,
+    addr: IpAddr,
+    observers: &mut [Box<dyn GameObserver>],
+) -> Result<(), Error<C::Error>> {
     let id = client.id();
     let start_pos = client.init(id).map_err(Error::Agent)?;    
     let mut tcp = TcpStream::connect((addr, port(id)))?;
@@ -170,30 +220,99 @@ pub fn run_client<C: GpwPlayer>(client: &mut C, addr: IpAddr) -> Result<(), Erro
     let init_pos: Vec<_> = start_pos.iter().map(|x| x.init_pos().unwrap()).collect();
     write!(tcp, "SET:{}\r\n", str::from_utf8(&init_pos).unwrap())?;
     expect_ok(&mut tcp)?;
+    let mut plies = match id {
+        PlayerID::P1 => 0,
+        PlayerID::P2 => 1,
+    };
+    // the board after the last move of the client
+    let mut last: Option<Board> = None;
+    // whether the last inferred move of the opponent was an escape
+    let mut escaped = false;
     loop {
-        let board = read(&mut tcp)?;
-        println!("{:?}", board);
-        match &board[..4] {
-            "MOV?" => {
-                let mov = client.gpw_step(&board[4..])?;
-                println!("{:?}", client.board());
-                tcp.write(mov.as_bytes())?;
-                expect_ok(&mut tcp)?;
-            },
-            "LST:" => {
-                println!("LOSE (´・ω・`)");
-                break;                
-            },
-            "WON:" => {
-                println!("WIN (*´ω｀*)");
-                break;
-            },
-            "DRW:" => {
-                println!("drow (>_<)");
-                break;
-            },
-            _ => return Err(Error::Mismatch(board.to_owned())),
+        let msg = read(&mut tcp)?;
+        println!("{:?}", msg);
+        let (head, body) = (&msg[..4], &msg[4..]);
+        let board = Board::from_gpw(body, id);
+        if let (Some(prev), Ok(board)) = (&last, &board) {
+            if let Ok(undo) = prev.infer_move(board, id.rev()) {
+                escaped = undo.escaped;
+                let mut board = prev.clone();
+                board.redo(undo);
+                notify(observers, id.rev(), undo.mov, undo.event(), &board);
+            }
         }
+        let captured = board.as_ref().map(|b| *b.captured()).unwrap_or_default();
+        let escaped = escaped || gpw_escaped(body);
+        if let Some(outcome) = gpw_outcome(head, &captured, escaped, id) {
+            match outcome.winner {
+                Some(winner) if winner == id => println!("WIN (*´ω｀*)"),
+                Some(_) => println!("LOSE (´・ω・`)"),
+                None => println!("drow (>_<)"),
+            }
+            for observer in observers.iter_mut() {
+                observer.on_end(outcome);
+            }
+            break;
+        }
+        if head != "MOV?" {
+            return Err(Error::Mismatch(msg.to_owned()));
+        }
+        let mut board = board?;
+        board.set_plies(plies);
+        if last.is_none() {
+            for observer in observers.iter_mut() {
+                observer.on_start(&board);
+            }
+        }
+        let (mov, own_mov, event) = client.gpw_step(&mut board)?;
+        notify(observers, id, own_mov, event, &board);
+        last = Some(board);
+        plies += 2;
+        println!("{:?}", client.board());
+        tcp.write_all(mov.as_bytes())?;
+        expect_ok(&mut tcp)?;
     }
     Ok(())
 }
+
+#[test]
+fn gpw_outcome_reasons() {
+    let outcome = |head: &str, own: &str, opponent: &str| {
+        let body = format!("{}{}", own, opponent);
+        let board = Board::from_gpw::<()>(&body, PlayerID::P1).unwrap();
+        gpw_outcome(head, board.captured(), gpw_escaped(&body), PlayerID::P1)
+    };
+    let (own, opponent) = ("14R24R34R44R15B25B35B45B", "41u31u21u11u40u30u20u10u");
+    // a blue ghost escaped and is out of the board
+    let escaped = "14R24R34R44R15B25B35B88B";
+    assert_eq!(
+        outcome("WON:", escaped, opponent),
+        Some(Outcome::win(PlayerID::P1, Reason::Escape))
+    );
+    // e.g. an illegal move or a timeout, which GPW doesn't tell
+    assert_eq!(
+        outcome("LST:", own, opponent),
+        Some(Outcome::win(PlayerID::P2, Reason::Unknown))
+    );
+    assert_eq!(
+        outcome("WON:", own, opponent),
+        Some(Outcome::win(PlayerID::P1, Reason::Unknown))
+    );
+    assert_eq!(
+        outcome("WON:", own, "41u31u21u11u99b99b99b99b"),
+        Some(Outcome::win(PlayerID::P1, Reason::AllBlueCaptured))
+    );
+    assert_eq!(
+        outcome("LST:", "14R24R34R44R99b99b99b99b", opponent),
+        Some(Outcome::win(PlayerID::P2, Reason::AllBlueCaptured))
+    );
+    assert_eq!(
+        outcome("LST:", own, "99r99r99r99r40u30u20u10u"),
+        Some(Outcome::win(PlayerID::P2, Reason::AllRedTaken))
+    );
+    assert_eq!(
+        outcome("DRW:", own, opponent),
+        Some(Outcome::draw(Reason::TurnLimit))
+    );
+    assert_eq!(outcome("MOV?", own, opponent), None);
+}